@@ -0,0 +1,440 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions,
+    game_time::GameTime,
+    menu::{return_to_menu, ButtonMaterials},
+    obstacles::{ObstacleCrossedEvent, ObstacleOutcome},
+    player::PlayerShip,
+    practice::PracticeMode,
+    profile::Profile,
+    score::Score,
+    skins::Skins,
+    GameState, SystemLabels,
+};
+
+/// How long each achievement toast is shown for, in seconds
+const TOAST_DURATION: f32 = 3.;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<AchievementProgress>()
+            .init_resource::<Toasts>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_achievement_progress.system())
+                    .with_system(spawn_toast_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(check_achievements.system().after(SystemLabels::UpdateScore))
+                    .with_system(update_toast_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_toast_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Achievements)
+                    .with_system(setup_achievements_screen.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Achievements)
+                    .with_system(click_achievements_button.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Achievements)
+                    .with_system(despawn_achievements_screen.system()),
+            );
+    }
+}
+
+/// Goals the player can reach during a run, which are saved to the profile once earned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Achievement {
+    Capture100Hay,
+    Survive3Minutes,
+    SteadyStrain,
+    NearMiss10,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::Capture100Hay,
+        Achievement::Survive3Minutes,
+        Achievement::SteadyStrain,
+        Achievement::NearMiss10,
+    ];
+
+    /// The id the achievement is saved under, and that skins are unlocked by
+    pub fn id(&self) -> &'static str {
+        match self {
+            Achievement::Capture100Hay => "capture_100_hay",
+            Achievement::Survive3Minutes => "survive_3_minutes",
+            Achievement::SteadyStrain => "steady_strain",
+            Achievement::NearMiss10 => "near_miss_10",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::Capture100Hay => "Hay Baron",
+            Achievement::Survive3Minutes => "Stayin' Alive",
+            Achievement::SteadyStrain => "Steady Hands",
+            Achievement::NearMiss10 => "Close Shave",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::Capture100Hay => "Capture 100 hay in one run",
+            Achievement::Survive3Minutes => "Survive for 3 minutes",
+            Achievement::SteadyStrain => "Keep the strain under 50% for 60 seconds",
+            Achievement::NearMiss10 => "Narrowly miss 10 obstacles in one run",
+        }
+    }
+
+    /// Whether the run so far has earned this achievement
+    fn is_earned(&self, progress: &AchievementProgress, score: &Score) -> bool {
+        match self {
+            Achievement::Capture100Hay => score.hay >= 100,
+            Achievement::Survive3Minutes => progress.survived >= 180.,
+            Achievement::SteadyStrain => progress.steady_time >= 60.,
+            Achievement::NearMiss10 => progress.near_misses >= 10,
+        }
+    }
+}
+
+/// Progress towards the achievements during the current run
+#[derive(Debug, Default)]
+pub struct AchievementProgress {
+    /// Practice runs can't fail, so they don't earn achievements
+    active: bool,
+
+    /// Real seconds survived, ignoring the game speeding up
+    survived: f32,
+
+    /// Real seconds since the strain was last above half
+    steady_time: f32,
+
+    near_misses: u32,
+}
+
+/// Messages waiting to pop up during play
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<String>,
+
+    /// Seconds left to show the message at the front of the queue
+    time_left: f32,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: String) {
+        if self.queue.is_empty() {
+            self.time_left = TOAST_DURATION;
+        }
+        self.queue.push_back(message);
+    }
+}
+
+struct ToastUiItem;
+struct ToastText;
+
+fn reset_achievement_progress(
+    practice: Res<PracticeMode>,
+    mut progress: ResMut<AchievementProgress>,
+) {
+    *progress = AchievementProgress {
+        active: !practice.enabled,
+        ..Default::default()
+    };
+}
+
+/// Tracks the run's progress and awards any achievements it has earned
+fn check_achievements(
+    time: Res<GameTime>,
+    ship: Res<PlayerShip>,
+    score: Res<Score>,
+    mut progress: ResMut<AchievementProgress>,
+    mut profile: ResMut<Profile>,
+    mut toasts: ResMut<Toasts>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+) {
+    if !progress.active || ship.is_dead {
+        return;
+    }
+
+    let real_delta = time.delta / time.multiplier;
+    progress.survived += real_delta;
+
+    if ship.strain_ratio() > 0.5 {
+        progress.steady_time = 0.;
+    } else {
+        progress.steady_time += real_delta;
+    }
+
+    for ev in crossed_events.iter() {
+        if ev.outcome == ObstacleOutcome::NearMiss {
+            progress.near_misses += 1;
+        }
+    }
+
+    for achievement in Achievement::ALL.iter() {
+        if achievement.is_earned(&progress, &score) && profile.unlock_achievement(achievement.id())
+        {
+            println!("Achievement unlocked: {}", achievement.name());
+            toasts.push(format!("Achievement unlocked: {}!", achievement.name()));
+            profile.save();
+        }
+    }
+}
+
+fn spawn_toast_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(20.)),
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(ToastUiItem)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: "".to_string(),
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 24.0,
+                                color: Color::rgb(0.9, 0.8, 0.2),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                })
+                .insert(ToastText);
+        });
+}
+
+/// Shows each toast in turn, in real time so they don't rush by as the game speeds up
+fn update_toast_ui(
+    time: Res<Time>,
+    mut toasts: ResMut<Toasts>,
+    mut toast_text: Query<&mut Text, With<ToastText>>,
+) {
+    if !toasts.queue.is_empty() {
+        toasts.time_left -= time.delta_seconds();
+        if toasts.time_left <= 0. {
+            toasts.queue.pop_front();
+            toasts.time_left = TOAST_DURATION;
+        }
+    }
+
+    let message = toasts.queue.front().cloned().unwrap_or_default();
+    for mut text in toast_text.iter_mut() {
+        if text.sections[0].value != message {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+fn despawn_toast_ui(
+    mut commands: Commands,
+    mut toasts: ResMut<Toasts>,
+    items: Query<Entity, With<ToastUiItem>>,
+) {
+    *toasts = Toasts::default();
+
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
+
+struct AchievementsItem;
+
+/// The back button on the achievements screen
+struct AchievementsBackButton;
+
+fn setup_achievements_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    button_materials: Res<ButtonMaterials>,
+    profile: Res<Profile>,
+    skins: Res<Skins>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(AchievementsItem)
+        .with_children(|node| {
+            node.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        left: Val::Px(0.),
+                        right: Val::Px(0.),
+                        top: Val::Px(0.),
+                        bottom: Val::Px(20.),
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Achievements".to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            for achievement in Achievement::ALL.iter() {
+                let earned = profile.has_achievement(achievement.id());
+
+                // mention any skin the achievement unlocks
+                let reward = skins
+                    .0
+                    .iter()
+                    .find(|skin| skin.unlock.as_deref() == Some(achievement.id()))
+                    .map(|skin| format!(", unlocks the {} skin", skin.name))
+                    .unwrap_or_default();
+
+                node.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(5.)),
+                        ..Default::default()
+                    },
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: format!(
+                                    "{} {}",
+                                    if earned { "[x]" } else { "[ ]" },
+                                    achievement.name()
+                                ),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 24.0,
+                                    color: if earned {
+                                        Color::rgb(0.9, 0.8, 0.2)
+                                    } else {
+                                        Color::rgb(0.6, 0.6, 0.6)
+                                    },
+                                },
+                            },
+                            TextSection {
+                                value: format!(" - {}{}", achievement.description(), reward),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            },
+                        ],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                });
+            }
+
+            node.spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                    margin: Rect {
+                        left: Val::Px(10.),
+                        right: Val::Px(10.),
+                        top: Val::Px(20.),
+                        bottom: Val::Px(0.),
+                    },
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: button_materials.normal.clone(),
+                ..Default::default()
+            })
+            .insert(AchievementsBackButton)
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: "Back".into(),
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 30.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                });
+            });
+        });
+}
+
+fn click_achievements_button(
+    button_materials: Res<ButtonMaterials>,
+    mut actions: ResMut<Actions>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut Handle<ColorMaterial>),
+        (Changed<Interaction>, With<AchievementsBackButton>),
+    >,
+) {
+    if return_to_menu(&mut actions, &mut state) {
+        return;
+    }
+
+    for (interaction, mut material) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                state.set(GameState::Menu).unwrap();
+                return;
+            }
+            Interaction::Hovered => {
+                *material = button_materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = button_materials.normal.clone();
+            }
+        }
+    }
+}
+
+fn despawn_achievements_screen(
+    mut commands: Commands,
+    items: Query<Entity, With<AchievementsItem>>,
+) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
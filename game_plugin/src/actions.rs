@@ -1,5 +1,14 @@
 use bevy::prelude::*;
 
+use crate::player::MAX_TRACTORS;
+
+/// The keys driving each tractor, from the leftmost: left, right, down and up
+const TRACTOR_KEYS: [[KeyCode; 4]; MAX_TRACTORS] = [
+    [KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W],
+    [KeyCode::J, KeyCode::L, KeyCode::K, KeyCode::I],
+    [KeyCode::Left, KeyCode::Right, KeyCode::Down, KeyCode::Up],
+];
+
 pub struct ActionsPlugin;
 
 impl Plugin for ActionsPlugin {
@@ -11,31 +20,88 @@ impl Plugin for ActionsPlugin {
 
 #[derive(Debug, Default)]
 pub struct Actions {
-    pub player_left_move: i8,
-    pub player_right_move: i8,
+    /// How each tractor is being driven across and up the lane, indexed by PlayerShipSide
+    pub tractor_moves: [i8; MAX_TRACTORS],
+    pub tractor_lifts: [i8; MAX_TRACTORS],
+
     pub restart_requested: bool,
+    pub shop_requested: bool,
+
+    /// Snaps the tractors together or apart along the tether
+    pub tether_snap_in: bool,
+    pub tether_snap_out: bool,
+
+    pub practice_toggle_freeze: bool,
+    pub practice_speed_change: i8,
+    pub practice_cycle_pattern: bool,
+
+    pub editor_select_pattern: i8,
+    pub editor_min_score_change: i8,
+    pub editor_weight_change: i8,
+    pub editor_new_pattern: bool,
+    pub editor_delete_pattern: bool,
+    pub editor_preview: bool,
+    pub editor_save: bool,
+    pub editor_exit: bool,
 }
 
 /// Queries actions every frame (allows navigation etc in the menu)
 fn set_movement_actions(mut actions: ResMut<Actions>, keyboard_input: Res<Input<KeyCode>>) {
-    actions.player_left_move = 0;
-    actions.player_right_move = 0;
+    for (idx, [left, right, down, up]) in TRACTOR_KEYS.iter().enumerate() {
+        actions.tractor_moves[idx] =
+            keyboard_input.pressed(*right) as i8 - keyboard_input.pressed(*left) as i8;
+        actions.tractor_lifts[idx] =
+            keyboard_input.pressed(*up) as i8 - keyboard_input.pressed(*down) as i8;
+    }
+
+    actions.restart_requested = keyboard_input.just_pressed(KeyCode::Space);
+    actions.shop_requested = keyboard_input.just_pressed(KeyCode::B);
+
+    actions.tether_snap_in = keyboard_input.just_pressed(KeyCode::Q);
+    actions.tether_snap_out = keyboard_input.just_pressed(KeyCode::E);
 
-    if keyboard_input.pressed(KeyCode::A) {
-        actions.player_left_move -= 1;
+    actions.practice_toggle_freeze = keyboard_input.just_pressed(KeyCode::F);
+    actions.practice_cycle_pattern = keyboard_input.just_pressed(KeyCode::P);
+
+    actions.practice_speed_change = 0;
+    if keyboard_input.just_pressed(KeyCode::LBracket) {
+        actions.practice_speed_change -= 1;
     }
 
-    if keyboard_input.pressed(KeyCode::D) {
-        actions.player_left_move += 1;
+    if keyboard_input.just_pressed(KeyCode::RBracket) {
+        actions.practice_speed_change += 1;
     }
 
-    if keyboard_input.pressed(KeyCode::J) {
-        actions.player_right_move -= 1;
+    actions.editor_select_pattern = 0;
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        actions.editor_select_pattern -= 1;
     }
 
-    if keyboard_input.pressed(KeyCode::L) {
-        actions.player_right_move += 1;
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        actions.editor_select_pattern += 1;
     }
 
-    actions.restart_requested = keyboard_input.just_pressed(KeyCode::Space);
+    actions.editor_min_score_change = 0;
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        actions.editor_min_score_change -= 1;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        actions.editor_min_score_change += 1;
+    }
+
+    actions.editor_weight_change = 0;
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        actions.editor_weight_change -= 1;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Equals) {
+        actions.editor_weight_change += 1;
+    }
+
+    actions.editor_new_pattern = keyboard_input.just_pressed(KeyCode::N);
+    actions.editor_delete_pattern = keyboard_input.just_pressed(KeyCode::Delete);
+    actions.editor_preview = keyboard_input.just_pressed(KeyCode::P);
+    actions.editor_save = keyboard_input.just_pressed(KeyCode::S);
+    actions.editor_exit = keyboard_input.just_pressed(KeyCode::Escape);
 }
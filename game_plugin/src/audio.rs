@@ -1,4 +1,8 @@
+use crate::audio_events::MAX_EFFECT_VOICES;
+use crate::game_time::GameTime;
 use crate::loading::AudioAssets;
+use crate::player::{PlayerShip, STRAIN_THRESHOLDS};
+use crate::settings::Settings;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin};
@@ -7,24 +11,124 @@ pub struct InternalAudioPlugin;
 
 struct AudioSpawned(bool);
 
+/// The music volume in the menu
+const MENU_MUSIC_VOLUME: f32 = 0.5;
+
+/// The music volume while playing, at the default intensity
+const GAME_MUSIC_VOLUME: f32 = 0.3;
+
+/// The music volume after the run ends, ducked under the game over sound
+const GAME_OVER_MUSIC_VOLUME: f32 = 0.15;
+
+/// The loudest the pulse and tension layers get
+const PULSE_VOLUME: f32 = 0.4;
+const TENSION_VOLUME: f32 = 0.6;
+
+/// The game speed at which the pulse layer is fully faded in
+const FULL_PULSE_MULTIPLIER: f32 = 3.;
+
+/// The strain at which the tension layer starts to fade in
+const TENSION_START: f32 = STRAIN_THRESHOLDS[0];
+
+/// How much each layer's volume can change per second, so every change is a crossfade
+const FADE_SPEED: f32 = 0.5;
+
+/// A stinger plays when the strain rises past each of these, and can play again once
+/// the strain has dropped back below the threshold by STINGER_RESET
+const STINGER_THRESHOLDS: [f32; 2] = [STRAIN_THRESHOLDS[1], STRAIN_THRESHOLDS[2]];
+const STINGER_RESET: f32 = 0.1;
+
+/// How intense the music should be while playing, from 0 (calm) to 1 (intense).
+/// Set by the wave script.
+pub struct MusicIntensity(pub f32);
+
+impl Default for MusicIntensity {
+    fn default() -> Self {
+        MusicIntensity(0.5)
+    }
+}
+
+/// The looped layers of the music. They all start together so they stay in time, and fade
+/// in and out as the game gets more intense.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MusicLayer {
+    /// The main track, heard everywhere
+    Base,
+
+    /// A shaker that fades in as the game speeds up
+    Pulse,
+
+    /// A low rumble that fades in as the tether strains
+    Tension,
+}
+
+impl MusicLayer {
+    const ALL: [MusicLayer; 3] = [MusicLayer::Base, MusicLayer::Pulse, MusicLayer::Tension];
+
+    fn channel<'a>(&self, channels: &'a AudioChannels) -> &'a AudioChannel {
+        match self {
+            MusicLayer::Base => &channels.music,
+            MusicLayer::Pulse => &channels.music_pulse,
+            MusicLayer::Tension => &channels.music_tension,
+        }
+    }
+}
+
+/// How loud each music layer is, and how loud it is fading towards, before the volume
+/// settings are applied. Indexed by MusicLayer.
+#[derive(Default)]
+struct MusicMix {
+    volumes: [f32; 3],
+    targets: [f32; 3],
+}
+
+/// Which stinger thresholds the strain is above, so each only plays once per rise
+#[derive(Default)]
+struct StingerState {
+    passed: [bool; 2],
+}
+
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(AudioChannels {
-            effects: AudioChannel::new("effects".to_owned()),
+            effect_voices: (0..MAX_EFFECT_VOICES)
+                .map(|voice| AudioChannel::new(format!("effects_{}", voice)))
+                .collect(),
             music: AudioChannel::new("music".to_owned()),
+            music_pulse: AudioChannel::new("music_pulse".to_owned()),
+            music_tension: AudioChannel::new("music_tension".to_owned()),
+            stinger: AudioChannel::new("stinger".to_owned()),
+            strain_warning: AudioChannel::new("strain_warning".to_owned()),
         })
         .insert_resource(AudioSpawned(false))
+        .init_resource::<MusicMix>()
+        .init_resource::<MusicIntensity>()
+        .init_resource::<StingerState>()
         .add_plugin(AudioPlugin)
+        .add_system(fade_music.system())
         .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(play_menu_music.system()))
         .add_system_set(
             SystemSet::on_enter(GameState::Playing).with_system(play_game_music.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(update_music_intensity.system())
+                .with_system(play_stingers.system()),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::GameOver).with_system(duck_game_over_music.system()),
         );
     }
 }
 
 pub struct AudioChannels {
-    pub effects: AudioChannel,
+    /// A channel per effect voice, so each effect can have its own volume and pitch
+    pub effect_voices: Vec<AudioChannel>,
     pub music: AudioChannel,
+    pub music_pulse: AudioChannel,
+    pub music_tension: AudioChannel,
+    pub stinger: AudioChannel,
+    pub strain_warning: AudioChannel,
 }
 
 fn play_menu_music(
@@ -32,18 +136,109 @@ fn play_menu_music(
     audio: Res<Audio>,
     channels: Res<AudioChannels>,
     mut audio_spawned: ResMut<AudioSpawned>,
+    mut mix: ResMut<MusicMix>,
 ) {
-    audio.set_volume_in_channel(0.5, &channels.music);
+    mix.targets = [MENU_MUSIC_VOLUME, 0., 0.];
 
     if audio_spawned.0 {
         return;
     }
 
     audio_spawned.0 = true;
-    audio.stop_channel(&channels.music);
+    for layer in MusicLayer::ALL.iter() {
+        audio.stop_channel(layer.channel(&channels));
+        audio.set_volume_in_channel(0., layer.channel(&channels));
+    }
     audio.play_looped_in_channel(audio_assets.music.clone(), &channels.music);
+    audio.play_looped_in_channel(audio_assets.music_pulse.clone(), &channels.music_pulse);
+    audio.play_looped_in_channel(audio_assets.music_tension.clone(), &channels.music_tension);
+}
+
+fn play_game_music(mut intensity: ResMut<MusicIntensity>, mut stingers: ResMut<StingerState>) {
+    *intensity = MusicIntensity::default();
+    *stingers = StingerState::default();
+}
+
+/// Fades the layers in with the wave's intensity, the game speed and the tether strain
+fn update_music_intensity(
+    intensity: Res<MusicIntensity>,
+    time: Res<GameTime>,
+    ship: Res<PlayerShip>,
+    mut mix: ResMut<MusicMix>,
+) {
+    if ship.is_dead {
+        return;
+    }
+
+    let speed = ((time.multiplier - 1.) / (FULL_PULSE_MULTIPLIER - 1.))
+        .max(0.)
+        .min(1.);
+    let strain = ((ship.strain_ratio() - TENSION_START) / (1. - TENSION_START))
+        .max(0.)
+        .min(1.);
+
+    mix.targets = [
+        GAME_MUSIC_VOLUME * (0.5 + intensity.0),
+        PULSE_VOLUME * speed.max(intensity.0 - 0.5),
+        TENSION_VOLUME * strain,
+    ];
+}
+
+fn duck_game_over_music(mut mix: ResMut<MusicMix>) {
+    mix.targets = [GAME_OVER_MUSIC_VOLUME, 0., 0.];
+}
+
+/// Plays a stinger when the strain rises past a threshold
+fn play_stingers(
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    ship: Res<PlayerShip>,
+    mut stingers: ResMut<StingerState>,
+) {
+    if ship.is_dead {
+        return;
+    }
+
+    let strain = ship.strain_ratio();
+    for (idx, threshold) in STINGER_THRESHOLDS.iter().enumerate() {
+        if !stingers.passed[idx] && strain >= *threshold {
+            stingers.passed[idx] = true;
+            audio.play_in_channel(audio_assets.stinger.clone(), &channels.stinger);
+        } else if stingers.passed[idx] && strain < threshold - STINGER_RESET {
+            stingers.passed[idx] = false;
+        }
+    }
 }
 
-fn play_game_music(audio: Res<Audio>, channels: Res<AudioChannels>) {
-    audio.set_volume_in_channel(0.3, &channels.music);
+/// Moves each layer towards its target volume and applies the music volume setting
+fn fade_music(
+    time: Res<Time>,
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    settings: Res<Settings>,
+    mut mix: ResMut<MusicMix>,
+) {
+    let max_step = FADE_SPEED * time.delta_seconds();
+
+    for (idx, layer) in MusicLayer::ALL.iter().enumerate() {
+        let step = (mix.targets[idx] - mix.volumes[idx])
+            .max(-max_step)
+            .min(max_step);
+        let fading = step.abs() > f32::EPSILON;
+        if fading {
+            mix.volumes[idx] += step;
+        }
+
+        if fading || settings.is_changed() {
+            audio.set_volume_in_channel(
+                mix.volumes[idx] * settings.music(),
+                layer.channel(&channels),
+            );
+        }
+    }
+
+    if settings.is_changed() {
+        audio.set_volume_in_channel(settings.music(), &channels.stinger);
+    }
 }
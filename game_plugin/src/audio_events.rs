@@ -1,21 +1,159 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
-use bevy_kira_audio::Audio;
+use bevy_kira_audio::{Audio, AudioSource};
+use rand::{thread_rng, Rng};
+
+use crate::{
+    actions::Actions,
+    audio::AudioChannels,
+    game_map::GameMap,
+    loading::AudioAssets,
+    player::{tether_middle_x, tractor_positions, PlayerShip, PlayerShipSide, STRAIN_THRESHOLDS},
+    settings::Settings,
+    GameState,
+};
+
+/// The most effects that can play at once, each in its own channel. Effects that would go
+/// over the limit are dropped.
+pub const MAX_EFFECT_VOICES: usize = 8;
+
+/// How far effects at the edge of the map are panned, from 0 (centred) to 1 (fully to
+/// one side). Kept short of fully panned so both ears always hear something.
+const PAN_WIDTH: f32 = 0.8;
 
-use crate::{audio::AudioChannels, loading::AudioAssets, GameState};
+/// Strain at which the tether starts creaking, and gets louder and faster
+const CREAK_THRESHOLDS: [(f32, AudioEffect); 3] = [
+    (STRAIN_THRESHOLDS[2], AudioEffect::CreakHigh),
+    (STRAIN_THRESHOLDS[1], AudioEffect::CreakMid),
+    (STRAIN_THRESHOLDS[0], AudioEffect::CreakLow),
+];
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AudioEffect {
     Collect,
+    TractorMove,
+    CreakLow,
+    CreakMid,
+    CreakHigh,
+    TetherSnap,
+    Crash,
+    PowerUp,
+    MenuHover,
+    MenuClick,
+    GameOver,
+}
+
+/// How an effect is played
+struct EffectSound {
+    volume: f32,
+    playback_rate: f32,
+
+    /// How far the volume and playback rate are randomly varied each time the effect plays,
+    /// as a fraction of their values
+    volume_variation: f32,
+    pitch_variation: f32,
+
+    /// The least time in seconds between plays of the effect
+    cooldown: f64,
+
+    /// How long the sound's file lasts in seconds at its normal rate. It holds a voice for
+    /// this long, scaled by the playback rate.
+    length: f64,
+}
+
+impl AudioEffect {
+    fn source(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            AudioEffect::Collect => audio_assets.collect.clone(),
+            AudioEffect::TractorMove => audio_assets.tractor_move.clone(),
+            AudioEffect::CreakLow | AudioEffect::CreakMid | AudioEffect::CreakHigh => {
+                audio_assets.creak.clone()
+            }
+            AudioEffect::TetherSnap => audio_assets.snap.clone(),
+            AudioEffect::Crash => audio_assets.crash.clone(),
+            AudioEffect::PowerUp => audio_assets.power_up.clone(),
+            AudioEffect::MenuHover => audio_assets.menu_hover.clone(),
+            AudioEffect::MenuClick => audio_assets.menu_click.clone(),
+            AudioEffect::GameOver => audio_assets.game_over.clone(),
+        }
+    }
+
+    fn sound(&self) -> EffectSound {
+        // the lengths are those of the files in assets/audio
+        let (volume, playback_rate, volume_variation, pitch_variation, cooldown, length) =
+            match self {
+                // collect.ogg
+                AudioEffect::Collect => (1., 1., 0.1, 0.08, 0.05, 0.14),
+                // tractor_move.wav
+                AudioEffect::TractorMove => (0.35, 1., 0.15, 0.1, 0.25, 0.22),
+                // creak.wav
+                AudioEffect::CreakLow => (0.3, 0.85, 0.2, 0.1, 1.5, 0.45),
+                AudioEffect::CreakMid => (0.5, 1., 0.2, 0.1, 1., 0.45),
+                AudioEffect::CreakHigh => (0.7, 1.25, 0.2, 0.1, 0.6, 0.45),
+                // snap.wav
+                AudioEffect::TetherSnap => (1., 1., 0.05, 0.05, 0.3, 0.5),
+                // crash.wav
+                AudioEffect::Crash => (1., 1., 0.1, 0.1, 0.3, 0.7),
+                // power_up.wav
+                AudioEffect::PowerUp => (0.8, 1., 0.05, 0., 0.3, 0.36),
+                // menu_hover.wav
+                AudioEffect::MenuHover => (0.4, 1., 0.1, 0.1, 0.05, 0.05),
+                // menu_click.wav
+                AudioEffect::MenuClick => (0.6, 1., 0.1, 0.05, 0.05, 0.08),
+                // game_over.ogg
+                AudioEffect::GameOver => (1., 1., 0., 0., 1., 0.65),
+            };
+
+        EffectSound {
+            volume,
+            playback_rate,
+            volume_variation,
+            pitch_variation,
+            cooldown,
+            length,
+        }
+    }
+}
+
+pub struct PlayAudioEffectEvent {
+    pub effect: AudioEffect,
+
+    /// Where in the world the sound comes from, or None to play it centred
+    pub x: Option<f32>,
 }
 
-pub struct PlayAudioEffectEvent(pub AudioEffect);
+impl PlayAudioEffectEvent {
+    /// An effect that doesn't come from anywhere in particular, such as a menu sound
+    pub fn centred(effect: AudioEffect) -> Self {
+        PlayAudioEffectEvent { effect, x: None }
+    }
+
+    /// An effect panned towards where it happened
+    pub fn at(effect: AudioEffect, x: f32) -> Self {
+        PlayAudioEffectEvent { effect, x: Some(x) }
+    }
+}
+
+/// Which effect voices are busy, and when each effect last played
+#[derive(Default)]
+struct EffectVoices {
+    busy_until: [f64; MAX_EFFECT_VOICES],
+    last_played: HashMap<AudioEffect, f64>,
+}
 
 pub struct AudioEventsPlugin;
 
 impl Plugin for AudioEventsPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_event::<PlayAudioEffectEvent>()
+            .init_resource::<EffectVoices>()
+            .add_system(handle_audio_events.system())
+            .add_system(send_button_sounds.system())
             .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(handle_audio_events.system()),
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(send_movement_sounds.system())
+                    .with_system(send_strain_creaks.system()),
             )
             .add_system_set(
                 SystemSet::on_enter(GameState::GameOver).with_system(play_game_over_sound.system()),
@@ -23,31 +161,141 @@ impl Plugin for AudioEventsPlugin {
     }
 }
 
+/// Plays each requested effect in a free voice, with a little variation so repeats don't
+/// sound identical, panned towards where it happened
 fn handle_audio_events(
-    audio_assets: Res<AudioAssets>,
+    time: Res<Time>,
+    game_map: Res<GameMap>,
+    audio_assets: Option<Res<AudioAssets>>,
     audio: Res<Audio>,
     channels: Res<AudioChannels>,
+    settings: Res<Settings>,
+    mut voices: ResMut<EffectVoices>,
     mut audio_events: EventReader<PlayAudioEffectEvent>,
 ) {
-    let mut has_played_collect = false;
+    // nothing can play until the sounds have loaded
+    let audio_assets = match audio_assets {
+        Some(audio_assets) => audio_assets,
+        None => return,
+    };
+
+    let now = time.seconds_since_startup();
+    let mut rng = thread_rng();
 
     for ev in audio_events.iter() {
-        match ev.0 {
-            AudioEffect::Collect => {
-                if !has_played_collect {
-                    has_played_collect = true;
-                    audio.play_in_channel(audio_assets.collect.clone(), &channels.effects)
-                }
+        let effect = ev.effect;
+        let sound = effect.sound();
+
+        if let Some(last_played) = voices.last_played.get(&effect) {
+            if now - last_played < sound.cooldown {
+                continue;
             }
         }
+
+        let voice = match voices.busy_until.iter().position(|until| *until <= now) {
+            Some(voice) => voice,
+            None => continue,
+        };
+
+        let volume = sound.volume
+            * (1. + rng.gen_range(-sound.volume_variation..=sound.volume_variation))
+            * settings.effects();
+        let playback_rate = sound.playback_rate
+            * (1. + rng.gen_range(-sound.pitch_variation..=sound.pitch_variation));
+        let panning = match ev.x {
+            Some(x) => {
+                let side = (x / game_map.get_x_bound()).max(-1.).min(1.);
+                0.5 + 0.5 * PAN_WIDTH * side
+            }
+            None => 0.5,
+        };
+
+        voices.busy_until[voice] = now + sound.length / playback_rate as f64;
+        voices.last_played.insert(effect, now);
+
+        let channel = &channels.effect_voices[voice];
+        audio.set_volume_in_channel(volume, channel);
+        audio.set_playback_rate_in_channel(playback_rate, channel);
+        audio.set_panning_in_channel(panning, channel);
+        audio.play_in_channel(effect.source(&audio_assets), channel);
     }
 }
 
-fn play_game_over_sound(
-    audio_assets: Res<AudioAssets>,
-    audio: Res<Audio>,
-    channels: Res<AudioChannels>,
+/// Ticks when a button is hovered and clicks when it is pressed, on every screen
+fn send_button_sounds(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut audio_events: EventWriter<PlayAudioEffectEvent>,
 ) {
+    for interaction in buttons.iter() {
+        match *interaction {
+            Interaction::Hovered => {
+                audio_events.send(PlayAudioEffectEvent::centred(AudioEffect::MenuHover))
+            }
+            Interaction::Clicked => {
+                audio_events.send(PlayAudioEffectEvent::centred(AudioEffect::MenuClick))
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+/// Chugs the engines while any tractor is driving, from between the tractors that are
+/// moving. The effect's cooldown sets the rhythm.
+fn send_movement_sounds(
+    actions: Res<Actions>,
+    ship: Res<PlayerShip>,
+    ship_sides: Query<(&Transform, &PlayerShipSide)>,
+    mut audio_events: EventWriter<PlayAudioEffectEvent>,
+) {
+    if ship.is_dead {
+        return;
+    }
+
+    let driving = ship_sides
+        .iter()
+        .filter(|(_, side)| {
+            actions.tractor_moves[side.0] != 0 || actions.tractor_lifts[side.0] != 0
+        })
+        .map(|(tx, _)| tx.translation.x)
+        .collect::<Vec<_>>();
+    if !driving.is_empty() {
+        let x = driving.iter().sum::<f32>() / driving.len() as f32;
+        audio_events.send(PlayAudioEffectEvent::at(AudioEffect::TractorMove, x));
+    }
+}
+
+/// Creaks the most strained tether, louder and more often as the strain builds
+fn send_strain_creaks(
+    ship: Res<PlayerShip>,
+    ship_sides: Query<(&Transform, &PlayerShipSide)>,
+    mut audio_events: EventWriter<PlayAudioEffectEvent>,
+) {
+    if ship.is_dead {
+        return;
+    }
+
+    let tether = ship.most_strained_tether();
+    let strain = ship.tether_strain_ratio(tether);
+    if let Some((_, creak)) = CREAK_THRESHOLDS
+        .iter()
+        .find(|(threshold, _)| strain >= *threshold)
+    {
+        let positions = tractor_positions(
+            ship_sides
+                .iter()
+                .map(|(tx, side)| (side.0, tx.translation.truncate())),
+        );
+        // the tractors may not have spawned yet at the start of a run
+        if positions.len() > tether + 1 {
+            audio_events.send(PlayAudioEffectEvent::at(
+                *creak,
+                tether_middle_x(&positions, tether),
+            ));
+        }
+    }
+}
+
+fn play_game_over_sound(mut audio_events: EventWriter<PlayAudioEffectEvent>) {
     println!("Playing game over sounds");
-    audio.play_in_channel(audio_assets.tether_break.clone(), &channels.effects)
+    audio_events.send(PlayAudioEffectEvent::centred(AudioEffect::GameOver));
 }
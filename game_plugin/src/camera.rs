@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    obstacles::{ObstacleCrossedEvent, ObstacleOutcome},
+    player::IsDead,
+    score::Score,
+    settings::Settings,
+    GameState, SystemLabels,
+};
+
+/// How much trauma an obstacle hitting a tractor adds, and how much losing the run adds.
+/// Trauma is capped at 1.
+const HIT_TRAUMA: f32 = 0.4;
+const FAILURE_TRAUMA: f32 = 0.8;
+
+/// How much trauma wears off each real second
+const TRAUMA_DECAY: f32 = 1.5;
+
+/// How far the camera shakes at full trauma and full screen shake, in world units and radians
+const MAX_SHAKE_OFFSET: f32 = 16.;
+const MAX_SHAKE_ANGLE: f32 = 0.04;
+
+/// How many captures in a row pulse the zoom
+const MIN_COMBO: u32 = 2;
+
+/// How far in the camera pulses on a combo, as a fraction of the view, and how long the
+/// pulse takes to settle in real seconds
+const COMBO_ZOOM: f32 = 0.04;
+const COMBO_ZOOM_TIME: f32 = 0.3;
+
+/// How long the slow motion lasts before the game over screen, in real seconds
+const SLOW_MOTION_TIME: f32 = 1.2;
+
+/// How slow the game gets at the end of the slow motion, and how far the camera zooms in
+const SLOW_MOTION_SPEED: f32 = 0.15;
+const SLOW_MOTION_ZOOM: f32 = 0.6;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<CameraEffects>()
+            .add_system(update_camera.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_camera.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    trigger_camera_effects
+                        .system()
+                        .label(SystemLabels::UpdateCamera),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(reset_camera_effects.system()),
+            );
+    }
+}
+
+/// The camera the game is played through
+pub struct GameCamera;
+
+/// The shake, zoom and slow motion currently playing on the game camera
+#[derive(Default)]
+pub struct CameraEffects {
+    /// How shaken up the camera is, from 0 (still) to 1. The shake grows with its square.
+    trauma: f32,
+
+    /// How much of the combo zoom pulse is left, from 1 (just started) to 0
+    zoom_pulse: f32,
+    combo: u32,
+
+    slow_motion: Option<SlowMotion>,
+}
+
+/// Slows the game and zooms in on where the run was lost
+struct SlowMotion {
+    focus: Vec2,
+    elapsed: f32,
+}
+
+impl CameraEffects {
+    /// Whether the game over screen should wait for the slow motion to finish
+    pub fn holding_game_over(&self) -> bool {
+        self.slow_motion
+            .as_ref()
+            .map_or(false, |slow_motion| slow_motion.elapsed < SLOW_MOTION_TIME)
+    }
+
+    fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.);
+    }
+}
+
+/// Spawns the game camera the first time a run starts, and reuses it after that
+fn spawn_camera(
+    mut commands: Commands,
+    mut effects: ResMut<CameraEffects>,
+    cameras: Query<Entity, With<GameCamera>>,
+) {
+    *effects = CameraEffects::default();
+
+    if cameras.iter().next().is_none() {
+        commands
+            .spawn_bundle(OrthographicCameraBundle::new_2d())
+            .insert(GameCamera);
+    }
+}
+
+/// Shakes the camera when obstacles hit the tractors or the run is lost, pulses the zoom on
+/// combos and starts the slow motion when the run is lost
+fn trigger_camera_effects(
+    settings: Res<Settings>,
+    score: Res<Score>,
+    mut effects: ResMut<CameraEffects>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+    failures: Query<&IsDead, Added<IsDead>>,
+) {
+    for ev in crossed_events.iter() {
+        if ev.outcome == ObstacleOutcome::HitTractor {
+            effects.add_trauma(HIT_TRAUMA);
+        }
+    }
+
+    if score.combo > effects.combo && score.combo >= MIN_COMBO {
+        effects.zoom_pulse = 1.;
+    }
+    effects.combo = score.combo;
+
+    for IsDead(_, point) in failures.iter() {
+        effects.add_trauma(FAILURE_TRAUMA);
+        if settings.camera_zoom {
+            effects.slow_motion = Some(SlowMotion {
+                focus: *point,
+                elapsed: 0.,
+            });
+        }
+    }
+}
+
+/// Moves the camera for the effects playing, in real time so it keeps up during slow motion
+fn update_camera(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut game_time: ResMut<GameTime>,
+    mut effects: ResMut<CameraEffects>,
+    mut cameras: Query<&mut Transform, With<GameCamera>>,
+) {
+    let delta = time.delta_seconds();
+    effects.trauma = (effects.trauma - TRAUMA_DECAY * delta).max(0.);
+    effects.zoom_pulse = (effects.zoom_pulse - delta / COMBO_ZOOM_TIME).max(0.);
+
+    let mut focus = Vec2::ZERO;
+    let mut zoom = 1.;
+    if settings.camera_zoom {
+        // ease in and back out over the pulse
+        zoom -= COMBO_ZOOM * (std::f32::consts::PI * effects.zoom_pulse).sin();
+    }
+    if let Some(slow_motion) = effects.slow_motion.as_mut() {
+        slow_motion.elapsed += delta;
+        let progress = (slow_motion.elapsed / SLOW_MOTION_TIME).min(1.);
+        let eased = progress * (2. - progress);
+
+        focus = slow_motion.focus * eased;
+        zoom *= 1. - (1. - SLOW_MOTION_ZOOM) * eased;
+        game_time.slow_motion = 1. - (1. - SLOW_MOTION_SPEED) * eased;
+    }
+
+    // shake with a few out of step waves, so it wobbles rather than jitters
+    let shake = settings.screen_shake * effects.trauma * effects.trauma;
+    let t = time.seconds_since_startup() as f32;
+    let wobble = |speed: f32, phase: f32| {
+        ((speed * t + phase).sin() + (2.3 * speed * t + 2. * phase).sin()) / 2.
+    };
+    let offset = MAX_SHAKE_OFFSET * shake * Vec2::new(wobble(31., 0.), wobble(37., 1.3));
+    let angle = MAX_SHAKE_ANGLE * shake * wobble(29., 2.7);
+
+    for mut tx in cameras.iter_mut() {
+        tx.translation.x = focus.x + offset.x;
+        tx.translation.y = focus.y + offset.y;
+        tx.rotation = Quat::from_rotation_z(angle);
+        tx.scale = Vec3::new(zoom, zoom, 1.);
+    }
+}
+
+/// Settles the camera back to normal once the game over screen is left
+fn reset_camera_effects(mut effects: ResMut<CameraEffects>, mut game_time: ResMut<GameTime>) {
+    *effects = CameraEffects::default();
+    game_time.slow_motion = 1.;
+}
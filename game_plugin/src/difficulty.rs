@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GAME_TIME_DOUBLING_TIME, obstacles::SPAWN_INTERVAL, player::MAX_SEPARATION_STRAIN,
+};
+
+/// The difficulty presets the player can choose from the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DifficultyPreset {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl DifficultyPreset {
+    pub const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Insane,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Insane => "Insane",
+        }
+    }
+
+    /// The preset after this one, wrapping back around to Easy
+    pub fn next(&self) -> Self {
+        match self {
+            DifficultyPreset::Easy => DifficultyPreset::Normal,
+            DifficultyPreset::Normal => DifficultyPreset::Hard,
+            DifficultyPreset::Hard => DifficultyPreset::Insane,
+            DifficultyPreset::Insane => DifficultyPreset::Easy,
+        }
+    }
+
+    /// Gets the game settings for this preset
+    pub fn settings(&self) -> Difficulty {
+        match self {
+            DifficultyPreset::Easy => Difficulty {
+                preset: *self,
+                doubling_time: 90.,
+                max_separation_strain: 10.,
+                strain_recovery_rate: 1.,
+                spawn_interval: 3.5,
+                obstacle_collision_distance: 0.7,
+                tractor_collision_distance: 0.65,
+            },
+            DifficultyPreset::Normal => Difficulty {
+                preset: *self,
+                doubling_time: GAME_TIME_DOUBLING_TIME,
+                max_separation_strain: MAX_SEPARATION_STRAIN,
+                strain_recovery_rate: 0.75,
+                spawn_interval: SPAWN_INTERVAL,
+                obstacle_collision_distance: 0.8,
+                tractor_collision_distance: 0.75,
+            },
+            DifficultyPreset::Hard => Difficulty {
+                preset: *self,
+                doubling_time: 45.,
+                max_separation_strain: 5.,
+                strain_recovery_rate: 0.6,
+                spawn_interval: 2.5,
+                obstacle_collision_distance: 0.85,
+                tractor_collision_distance: 0.8,
+            },
+            DifficultyPreset::Insane => Difficulty {
+                preset: *self,
+                doubling_time: 30.,
+                max_separation_strain: 4.,
+                strain_recovery_rate: 0.5,
+                spawn_interval: 2.,
+                obstacle_collision_distance: 0.9,
+                tractor_collision_distance: 0.9,
+            },
+        }
+    }
+}
+
+/// The active difficulty settings, set from the preset chosen in the menu
+#[derive(Debug, Clone, Copy)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+
+    /// Seconds for the game speed to double
+    pub doubling_time: f32,
+
+    /// How much strain the tether can take before it breaks
+    pub max_separation_strain: f32,
+
+    /// How quickly strain drops while the tractors are close enough
+    pub strain_recovery_rate: f32,
+
+    /// Seconds between obstacle spawns
+    pub spawn_interval: f32,
+
+    /// How close (in sprites) an obstacle needs to be to a tractor to hit it
+    pub obstacle_collision_distance: f32,
+
+    /// How close (in sprites) the tractors need to be to crash into each other
+    pub tractor_collision_distance: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        DifficultyPreset::Normal.settings()
+    }
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Difficulty>();
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    obstacles::{patterns::SpawnPattern, ObstacleCrossedEvent, ObstacleOutcome},
+    player::PlayerShip,
+    practice::PracticeMode,
+    GameState, SystemLabels,
+};
+
+/// How quickly the performance averages follow new results, higher reacts faster
+const OBSTACLE_SMOOTHING: f32 = 0.2;
+const STRAIN_SMOOTHING: f32 = 0.5;
+
+/// The band of player stress (0 = bored, 1 = overwhelmed) the director aims for
+const TARGET_STRESS_MIN: f32 = 0.35;
+const TARGET_STRESS_MAX: f32 = 0.55;
+
+/// How quickly (per second) the director adjusts difficulty to get back in the band
+const ADJUST_RATE: f32 = 0.05;
+
+/// Adapts obstacle spawning to how well the player is doing, keeping runs in a
+/// target intensity band. Difficulty runs from -1 (easiest) to 1 (hardest).
+pub struct DifficultyDirector {
+    /// Disable for competitive modes so every run plays the same
+    pub enabled: bool,
+    pub difficulty: f32,
+
+    capture_rate: f32,
+    near_miss_rate: f32,
+    average_strain: f32,
+    active: bool,
+}
+
+impl Default for DifficultyDirector {
+    fn default() -> Self {
+        DifficultyDirector {
+            enabled: true,
+            difficulty: 0.,
+            capture_rate: 0.5,
+            near_miss_rate: 0.,
+            average_strain: 0.,
+            active: false,
+        }
+    }
+}
+
+impl DifficultyDirector {
+    /// Estimates how stressed the player is from their recent performance
+    pub fn stress(&self) -> f32 {
+        0.4 * (1. - self.capture_rate) + 0.4 * self.average_strain + 0.2 * self.near_miss_rate
+    }
+
+    /// The difficulty currently being applied, which is neutral when the director is off
+    fn applied_difficulty(&self) -> f32 {
+        if self.active {
+            self.difficulty
+        } else {
+            0.
+        }
+    }
+
+    /// Multiplier for the time between obstacle spawns
+    pub fn spawn_interval_scale(&self) -> f32 {
+        1. - 0.35 * self.applied_difficulty()
+    }
+
+    /// Multiplier for the speed obstacles fall at
+    pub fn fall_speed_scale(&self) -> f32 {
+        1. + 0.25 * self.applied_difficulty()
+    }
+
+    /// Selection weight for a spawn pattern, favouring patterns with more obstacles
+    /// as difficulty rises and fewer as it drops
+    pub fn pattern_weight(&self, pattern: &SpawnPattern) -> f32 {
+        let size = pattern.offsets.len() as f32;
+        (1. + self.applied_difficulty() * (size - 3.) / 3.).max(0.1)
+    }
+}
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<DifficultyDirector>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_director.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    update_director
+                        .system()
+                        .after(SystemLabels::MoveObstacles)
+                        .after(SystemLabels::MovePlayer),
+                ),
+            );
+    }
+}
+
+/// Resets the director at the start of a run. Practice runs have their own controls
+/// so the director stays out of the way
+fn reset_director(practice: Res<PracticeMode>, mut director: ResMut<DifficultyDirector>) {
+    *director = DifficultyDirector {
+        enabled: director.enabled,
+        active: director.enabled && !practice.enabled,
+        ..Default::default()
+    };
+}
+
+/// Tracks recent player performance and nudges the difficulty towards the target band
+fn update_director(
+    time: Res<GameTime>,
+    ship: Res<PlayerShip>,
+    mut director: ResMut<DifficultyDirector>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+) {
+    if !director.active || ship.is_dead {
+        return;
+    }
+
+    for ev in crossed_events.iter() {
+        let (captured, near_miss) = match ev.outcome {
+            ObstacleOutcome::Captured => (1., 0.),
+            ObstacleOutcome::Missed => (0., 0.),
+            ObstacleOutcome::NearMiss | ObstacleOutcome::HitTractor => (0., 1.),
+        };
+
+        director.capture_rate += OBSTACLE_SMOOTHING * (captured - director.capture_rate);
+        director.near_miss_rate += OBSTACLE_SMOOTHING * (near_miss - director.near_miss_rate);
+    }
+
+    let strain = ship.strain_ratio().min(1.);
+    let strain_factor = (STRAIN_SMOOTHING * time.delta).min(1.);
+    director.average_strain += strain_factor * (strain - director.average_strain);
+
+    let stress = director.stress();
+    if stress < TARGET_STRESS_MIN {
+        director.difficulty = (director.difficulty + ADJUST_RATE * time.delta).min(1.);
+    } else if stress > TARGET_STRESS_MAX {
+        director.difficulty = (director.difficulty - ADJUST_RATE * time.delta).max(-1.);
+    }
+}
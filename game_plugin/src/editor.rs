@@ -0,0 +1,550 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions,
+    difficulty::Difficulty,
+    game_map::GameMap,
+    loading::{paths::PATHS, TextureAssets},
+    obstacles::{
+        pattern_file::write_spawn_patterns,
+        patterns::{AvailableSpawnPatterns, SpawnPattern},
+        validation::{validate_spawn_patterns, PatternIssueKind, SolvabilityParams},
+        waves::{WaveInstruction, WaveScript},
+        OBSTACLE_FALL_SPEED, VALIDATION_MULTIPLIER,
+    },
+    player::MAX_TRACTORS,
+    storage::asset_path,
+    GameState, SystemLabels,
+};
+
+/// How many cells the editor grid extends either side of the pattern center
+const GRID_HALF_WIDTH: i32 = 6;
+const GRID_HALF_HEIGHT: i32 = 3;
+
+/// How far (in sprites) the grid center sits below the top of the map
+const GRID_CENTER_FROM_TOP: f32 = 5.;
+
+const MIN_SCORE_STEP: f32 = 10.;
+const WEIGHT_STEP: f32 = 0.1;
+
+/// The patterns being edited, which only replace the game's patterns when saved
+pub struct PatternEditor {
+    patterns: AvailableSpawnPatterns,
+    selected: usize,
+    status: String,
+    issues: String,
+}
+
+pub struct EditorItem;
+pub struct EditorText;
+
+/// A cloud shown in the editor grid at a sprite sized offset from the pattern center
+pub struct EditorCell(i32, i32);
+
+/// A cloud from a previewed pattern falling towards the tractors
+pub struct EditorPreview;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Editor).with_system(setup_editor.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Editor)
+                .with_system(edit_patterns.system().label(SystemLabels::EditPatterns))
+                .with_system(
+                    update_editor_grid
+                        .system()
+                        .after(SystemLabels::EditPatterns),
+                )
+                .with_system(
+                    update_editor_text
+                        .system()
+                        .after(SystemLabels::EditPatterns),
+                )
+                .with_system(move_preview.system()),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Editor).with_system(despawn_editor.system()));
+    }
+}
+
+impl PatternEditor {
+    fn selected_pattern(&mut self) -> &mut SpawnPattern {
+        &mut self.patterns.patterns[self.selected]
+    }
+
+    /// Toggles a cloud in the selected pattern
+    fn toggle_cell(&mut self, x: i32, y: i32) {
+        let offsets = &mut self.selected_pattern().offsets;
+        match offsets.iter().position(|offset| is_cell(offset, x, y)) {
+            Some(idx) => {
+                offsets.remove(idx);
+            }
+            None => offsets.push(Vec2::new(x as f32, y as f32)),
+        }
+    }
+
+    /// Removes the selected pattern, along with any sequences that spawn it. Patterns after
+    /// it move down one, so it can't be deleted while the wave script spawns it or any
+    /// pattern after it by index.
+    fn delete_selected(&mut self, script: &WaveScript) {
+        if self.patterns.patterns.len() <= 1 {
+            self.status = "Can't delete the last pattern".into();
+            return;
+        }
+
+        let removed = self.selected;
+        let mut scripted = script
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                WaveInstruction::Pattern(idx) if *idx >= removed => Some(*idx),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !scripted.is_empty() {
+            scripted.sort_unstable();
+            scripted.dedup();
+            let scripted = scripted
+                .iter()
+                .map(|idx| idx.to_string())
+                .collect::<Vec<_>>();
+            self.status = format!(
+                "Can't delete pattern {}, the wave script uses pattern {}",
+                removed + 1,
+                scripted.join(", ")
+            );
+            return;
+        }
+
+        self.patterns.patterns.remove(removed);
+        let sequences = self.patterns.sequences.len();
+        self.patterns
+            .sequences
+            .retain(|sequence| sequence.steps.iter().all(|step| step.pattern != removed));
+        let dropped = sequences - self.patterns.sequences.len();
+        for sequence in self.patterns.sequences.iter_mut() {
+            for step in sequence.steps.iter_mut() {
+                if step.pattern > removed {
+                    step.pattern -= 1;
+                }
+            }
+        }
+
+        self.selected = self.selected.min(self.patterns.patterns.len() - 1);
+        self.status = match dropped {
+            0 => format!("Deleted pattern {}", removed + 1),
+            1 => format!("Deleted pattern {} and the sequence using it", removed + 1),
+            _ => format!(
+                "Deleted pattern {} and the {} sequences using it",
+                removed + 1,
+                dropped
+            ),
+        };
+    }
+
+    /// Checks the selected pattern can be avoided and captured from every spawn position,
+    /// with every number of tractors
+    fn check_selected(&mut self, game_map: &GameMap, difficulty: &Difficulty) {
+        let single = AvailableSpawnPatterns {
+            patterns: vec![self.patterns.patterns[self.selected].clone()],
+            sequences: vec![],
+        };
+
+        if single.patterns[0].offsets.is_empty() {
+            self.issues = "Empty patterns can't be saved".into();
+            return;
+        }
+
+        let issues = (2..=MAX_TRACTORS)
+            .flat_map(|tractors| {
+                let params =
+                    SolvabilityParams::new(game_map, difficulty, VALIDATION_MULTIPLIER, tractors);
+                validate_spawn_patterns(&single, game_map, &params)
+            })
+            .collect::<Vec<_>>();
+
+        // a spawn position only counts once, however many tractor counts it fails with
+        let count = |kind| {
+            let mut spawn_xs = issues
+                .iter()
+                .filter(|issue| issue.kind == kind)
+                .map(|issue| issue.spawn_x)
+                .collect::<Vec<_>>();
+            spawn_xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            spawn_xs.dedup();
+            spawn_xs.len()
+        };
+
+        self.issues = if issues.is_empty() {
+            "Solvable from every spawn position".into()
+        } else {
+            format!(
+                "Off the map at {}, unavoidable at {}, uncapturable at {} spawn positions",
+                count(PatternIssueKind::OutsideBounds),
+                count(PatternIssueKind::Unavoidable),
+                count(PatternIssueKind::Uncapturable)
+            )
+        };
+    }
+
+    /// Writes the patterns to the pattern file the game loads, and starts using them
+    fn save(&mut self, game_patterns: &mut AvailableSpawnPatterns) {
+        if let Some(idx) = self
+            .patterns
+            .patterns
+            .iter()
+            .position(|pattern| pattern.offsets.is_empty())
+        {
+            self.status = format!("Pattern {} is empty, add a cloud or delete it", idx + 1);
+            return;
+        }
+
+        let path = match asset_path(PATHS.spawn_patterns) {
+            Some(path) => path,
+            None => {
+                self.status = "Saving patterns isn't supported here".into();
+                return;
+            }
+        };
+
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                self.status = format!("Unable to create {:?}: {}", dir, e);
+                return;
+            }
+        }
+
+        match std::fs::write(&path, write_spawn_patterns(&self.patterns)) {
+            Ok(_) => {
+                *game_patterns = self.patterns.clone();
+                self.status = format!("Saved to {:?}", path);
+            }
+            Err(e) => {
+                self.status = format!("Unable to save {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Checks if an offset falls in a grid cell
+fn is_cell(offset: &Vec2, x: i32, y: i32) -> bool {
+    offset.x.round() as i32 == x && offset.y.round() as i32 == y
+}
+
+/// The world position of the grid center
+fn grid_center(game_map: &GameMap) -> Vec2 {
+    Vec2::new(
+        0.,
+        game_map.top_y() - GRID_CENTER_FROM_TOP * game_map.sprite_size,
+    )
+}
+
+/// Spawns the editor grid, the tractors the preview falls towards and the editor text
+fn setup_editor(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    textures: Res<TextureAssets>,
+    game_map: Res<GameMap>,
+    difficulty: Res<Difficulty>,
+    patterns: Res<AvailableSpawnPatterns>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let mut editor = PatternEditor {
+        patterns: patterns.clone(),
+        selected: 0,
+        status: "".into(),
+        issues: "".into(),
+    };
+    editor.check_selected(&game_map, &difficulty);
+    commands.insert_resource(editor);
+
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(EditorItem);
+
+    let center = grid_center(&game_map);
+    let cell_material = materials.add(Color::rgba(1., 1., 1., 0.1).into());
+    let cloud_atlas = texture_atlases.add(TextureAtlas::from_grid(
+        textures.cloud_001.clone(),
+        Vec2::new(32., 32.),
+        4,
+        1,
+    ));
+
+    for x in -GRID_HALF_WIDTH..=GRID_HALF_WIDTH {
+        for y in -GRID_HALF_HEIGHT..=GRID_HALF_HEIGHT {
+            let position = center + Vec2::new(x as f32, y as f32) * game_map.sprite_size;
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: cell_material.clone(),
+                    sprite: Sprite::new(Vec2::splat(game_map.sprite_size - 2.)),
+                    transform: Transform::from_translation(position.extend(0.5)),
+                    ..Default::default()
+                })
+                .insert(EditorItem);
+
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: cloud_atlas.clone(),
+                    transform: Transform::from_translation(position.extend(1.)),
+                    ..Default::default()
+                })
+                .insert(EditorCell(x, y))
+                .insert(EditorItem);
+        }
+    }
+
+    // tractors at the default tether length to judge the pattern against
+    let tractor_x = 2.5 * game_map.sprite_size;
+    for (texture, x) in [
+        (textures.player_left.clone(), -tractor_x),
+        (textures.player_right.clone(), tractor_x),
+    ]
+    .iter()
+    {
+        let atlas = TextureAtlas::from_grid(texture.clone(), Vec2::new(32., 32.), 4, 1);
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: texture_atlases.add(atlas),
+                transform: Transform::from_translation(Vec3::new(*x, game_map.bottom_y(), 1.)),
+                ..Default::default()
+            })
+            .insert(EditorItem);
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::FlexStart,
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(EditorItem)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: "".to_string(),
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                })
+                .insert(EditorText);
+
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Click: toggle cloud, Left/Right: pattern, Up/Down: min score, \
+                                -/=: weight, N: new, Delete: remove, P: preview, S: save, \
+                                Esc: menu"
+                            .to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 14.0,
+                            color: Color::rgb(0.6, 0.6, 0.6),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+        });
+}
+
+/// Applies editor input to the selected pattern
+fn edit_patterns(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    textures: Res<TextureAssets>,
+    game_map: Res<GameMap>,
+    difficulty: Res<Difficulty>,
+    script: Res<WaveScript>,
+    mut editor: ResMut<PatternEditor>,
+    mut game_patterns: ResMut<AvailableSpawnPatterns>,
+    mut state: ResMut<State<GameState>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    if actions.editor_exit {
+        state.set(GameState::Menu).unwrap();
+        return;
+    }
+
+    let mut changed = false;
+
+    if actions.editor_select_pattern != 0 {
+        let count = editor.patterns.patterns.len() as i32;
+        editor.selected = (editor.selected as i32 + actions.editor_select_pattern as i32)
+            .rem_euclid(count) as usize;
+        changed = true;
+    }
+
+    if actions.editor_new_pattern {
+        editor.patterns.patterns.push(SpawnPattern {
+            offsets: vec![],
+            ..Default::default()
+        });
+        editor.selected = editor.patterns.patterns.len() - 1;
+        editor.status = "Added a new pattern".into();
+        changed = true;
+    }
+
+    if actions.editor_delete_pattern {
+        editor.delete_selected(&script);
+        changed = true;
+    }
+
+    if actions.editor_min_score_change != 0 {
+        let pattern = editor.selected_pattern();
+        pattern.min_score =
+            (pattern.min_score + actions.editor_min_score_change as f32 * MIN_SCORE_STEP).max(-1.);
+    }
+
+    if actions.editor_weight_change != 0 {
+        let pattern = editor.selected_pattern();
+        pattern.weight = (pattern.weight + actions.editor_weight_change as f32 * WEIGHT_STEP)
+            .max(0.)
+            .min(10.);
+        // keep the weight tidy in the pattern file
+        pattern.weight = (pattern.weight * 10.).round() / 10.;
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let window = windows.get_primary().unwrap();
+        if let Some(cursor) = window.cursor_position() {
+            // the camera is centered on the origin, but the cursor is from the bottom left
+            let world = cursor - Vec2::new(window.width() / 2., window.height() / 2.);
+            let cell = (world - grid_center(&game_map)) / game_map.sprite_size;
+            let (x, y) = (cell.x.round() as i32, cell.y.round() as i32);
+
+            if x.abs() <= GRID_HALF_WIDTH && y.abs() <= GRID_HALF_HEIGHT {
+                editor.toggle_cell(x, y);
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        editor.check_selected(&game_map, &difficulty);
+    }
+
+    if actions.editor_preview {
+        spawn_preview(
+            &mut commands,
+            &textures,
+            &game_map,
+            &mut texture_atlases,
+            &editor.patterns.patterns[editor.selected],
+        );
+    }
+
+    if actions.editor_save {
+        editor.save(&mut game_patterns);
+    }
+}
+
+/// Spawns a copy of the pattern over the grid that falls towards the tractors
+fn spawn_preview(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    game_map: &GameMap,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    pattern: &SpawnPattern,
+) {
+    let texture_atlas =
+        TextureAtlas::from_grid(textures.cloud_001.clone(), Vec2::new(32., 32.0), 4, 1);
+    let texture_atlas_handle = texture_atlases.add(texture_atlas);
+    let center = grid_center(game_map);
+
+    for offset in pattern.offsets.iter() {
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: texture_atlas_handle.clone(),
+                transform: Transform::from_translation(
+                    (center + *offset * game_map.sprite_size).extend(2.),
+                ),
+                ..Default::default()
+            })
+            .insert(Timer::from_seconds(0.2, true))
+            .insert(EditorPreview)
+            .insert(EditorItem);
+    }
+}
+
+/// Moves previewed clouds down at the base fall speed, removing them once past the tractors
+fn move_preview(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_map: Res<GameMap>,
+    mut previews: Query<
+        (Entity, &mut Transform, &mut Timer, &mut TextureAtlasSprite),
+        With<EditorPreview>,
+    >,
+) {
+    let min_y = game_map.bottom_y() - game_map.pad_y * game_map.sprite_size;
+
+    for (entity, mut tx, mut timer, mut sprite) in previews.iter_mut() {
+        timer.tick(time.delta());
+        if timer.just_finished() {
+            sprite.index = (sprite.index + 1) % 4;
+        }
+
+        tx.translation.y -= OBSTACLE_FALL_SPEED * time.delta_seconds();
+        if tx.translation.y < min_y {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Shows the clouds of the selected pattern in the grid
+fn update_editor_grid(editor: Res<PatternEditor>, mut cells: Query<(&EditorCell, &mut Visible)>) {
+    let offsets = &editor.patterns.patterns[editor.selected].offsets;
+
+    for (cell, mut visible) in cells.iter_mut() {
+        visible.is_visible = offsets.iter().any(|offset| is_cell(offset, cell.0, cell.1));
+    }
+}
+
+/// Shows the settings of the selected pattern
+fn update_editor_text(editor: Res<PatternEditor>, mut text: Query<&mut Text, With<EditorText>>) {
+    let pattern = &editor.patterns.patterns[editor.selected];
+
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!(
+            "Pattern {}/{}  Min score: {:.0}  Weight: {:.1}  Clouds: {}\n{}\n{}",
+            editor.selected + 1,
+            editor.patterns.patterns.len(),
+            pattern.min_score,
+            pattern.weight,
+            pattern.offsets.len(),
+            editor.issues,
+            editor.status
+        );
+    }
+}
+
+/// despawns the editor
+fn despawn_editor(mut commands: Commands, items: Query<Entity, With<EditorItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
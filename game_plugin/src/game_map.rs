@@ -4,6 +4,9 @@ pub struct GameMap {
     pub pad_x: f32,
     pub pad_y: f32,
     pub sprite_size: f32,
+
+    /// How far (in sprites) the tractors can drive up from the bottom of the map
+    pub lane_height: f32,
 }
 
 impl Default for GameMap {
@@ -14,6 +17,7 @@ impl Default for GameMap {
             pad_x: 3.,
             pad_y: 3.,
             sprite_size: 32.,
+            lane_height: 3.,
         }
     }
 }
@@ -24,6 +28,11 @@ impl GameMap {
         -(self.height / 2.) * self.sprite_size
     }
 
+    /// Determines the highest the tractors can drive in world coordinates
+    pub fn lane_top_y(&self) -> f32 {
+        self.bottom_y() + self.lane_height * self.sprite_size
+    }
+
     /// Determines the "top" of the map in world coordinates
     pub fn top_y(&self) -> f32 {
         (self.height / 2.) * self.sprite_size
@@ -1,8 +1,13 @@
 use crate::{
     actions::Actions,
-    player::{IsDead, PlayerShip},
-    score::Score,
-    GameState,
+    camera::CameraEffects,
+    difficulty::Difficulty,
+    menu::return_to_menu,
+    player::{IsDead, PlayerShip, TractorCount},
+    profile::Profile,
+    run_stats::{spawn_run_breakdown, RunStats},
+    score::{mode_name, HighScores, Score},
+    GameState, SystemLabels,
 };
 use bevy::prelude::*;
 
@@ -11,7 +16,11 @@ pub struct GameOverPlugin;
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_system_set(
-            SystemSet::on_update(GameState::Playing).with_system(transition_to_game_over.system()),
+            SystemSet::on_update(GameState::Playing).with_system(
+                transition_to_game_over
+                    .system()
+                    .after(SystemLabels::UpdateCamera),
+            ),
         )
         .add_system_set(
             SystemSet::on_update(GameState::GameOver).with_system(restart_game.system()),
@@ -28,6 +37,12 @@ fn transition_to_game_over(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     score: Res<Score>,
+    stats: Res<RunStats>,
+    difficulty: Res<Difficulty>,
+    tractor_count: Res<TractorCount>,
+    camera_effects: Res<CameraEffects>,
+    mut high_scores: ResMut<HighScores>,
+    mut profile: ResMut<Profile>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     players: Query<&IsDead>,
     mut state: ResMut<State<GameState>>,
@@ -37,7 +52,32 @@ fn transition_to_game_over(
         return;
     }
 
-    let reason = dead_player.unwrap().0.clone();
+    // let the slow motion zoom into the failure play out first
+    if camera_effects.holding_game_over() {
+        return;
+    }
+
+    let cause = dead_player.unwrap().0;
+
+    let mode = (difficulty.preset, tractor_count.0);
+    let high_score_message = if high_scores.submit(mode, score.current) {
+        high_scores.save();
+        format!("New {} high score!", mode_name(mode))
+    } else {
+        format!(
+            "{} high score: {:.0}",
+            mode_name(mode),
+            high_scores.get(mode)
+        )
+    };
+
+    // the run is added to the lifetime stats, and its hay banked for the shop
+    profile.record_run(cause, stats.duration, score.hay);
+    profile.save();
+    let hay_message = format!(
+        "You harvested {} hay, and have {} hay to spend in the shop",
+        score.hay, profile.hay
+    );
 
     commands
         .spawn_bundle(NodeBundle {
@@ -58,7 +98,7 @@ fn transition_to_game_over(
                     sections: vec![TextSection {
                         value: format!(
                             "Oh Noooo! {} You scored {:.0}",
-                            reason,
+                            cause.message(),
                             score.current.floor()
                         ),
                         style: TextStyle {
@@ -75,7 +115,39 @@ fn transition_to_game_over(
             node.spawn_bundle(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: "Hit space to return to the menu".into(),
+                        value: high_score_message,
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.3, 0.3, 0.3),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            node.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: hay_message,
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.3, 0.3, 0.3),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            spawn_run_breakdown(node, &stats, &asset_server, &mut materials);
+
+            node.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Hit space to return to the menu, or B to visit the shop".into(),
                         style: TextStyle {
                             font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
                             font_size: 20.0,
@@ -105,9 +177,11 @@ fn restart_game(
         return;
     }
 
-    if actions.restart_requested {
-        state.set(GameState::Menu).unwrap();
-        actions.restart_requested = false;
+    if return_to_menu(&mut actions, &mut state) {
         return;
     }
+
+    if actions.shop_requested {
+        state.set(GameState::Shop).unwrap();
+    }
 }
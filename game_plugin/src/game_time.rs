@@ -2,9 +2,9 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 
-use crate::{GameState, SystemLabels};
+use crate::{difficulty::Difficulty, practice::PracticeMode, GameState, SystemLabels};
 
-pub const GAME_TIME_DOUBLING_TIME: f32 = 60.; // e.g. 60 == double speed every minute
+pub const GAME_TIME_DOUBLING_TIME: f32 = 60.; // e.g. 60 == double speed every minute, on Normal difficulty
 
 pub struct GameTime {
     pub multiplier: f32,
@@ -12,6 +12,9 @@ pub struct GameTime {
     pub delta: f32,
     pub delta_duration: Duration,
     pub fixed_update: bool,
+
+    /// Slows the game down on top of the multiplier, 1 for normal speed
+    pub slow_motion: f32,
     next_fixed_update: f64,
 }
 
@@ -25,6 +28,7 @@ impl Plugin for GameTimePlugin {
             delta: 0.,
             delta_duration: Duration::from_secs(0),
             fixed_update: false,
+            slow_motion: 1.,
             next_fixed_update: 0.5,
         })
         .add_system_set(
@@ -45,16 +49,18 @@ fn setup_game_time(mut game_time: ResMut<GameTime>) {
     game_time.delta = 0.;
     game_time.delta_duration = Duration::from_secs(0);
     game_time.fixed_update = false;
+    game_time.slow_motion = 1.;
     game_time.next_fixed_update = 0.5;
 }
 
 /// Updates the game timer
 fn update_game_time(time: Res<Time>, mut game_time: ResMut<GameTime>) {
-    let dt = time.delta_seconds() * game_time.multiplier;
+    let speed = game_time.multiplier * game_time.slow_motion;
+    let dt = time.delta_seconds() * speed;
 
     game_time.elapsed += dt;
     game_time.delta = dt;
-    game_time.delta_duration = time.delta().mul_f32(game_time.multiplier);
+    game_time.delta_duration = time.delta().mul_f32(speed);
 
     if time.seconds_since_startup() > game_time.next_fixed_update {
         game_time.next_fixed_update = time.seconds_since_startup() + 0.1;
@@ -64,6 +70,18 @@ fn update_game_time(time: Res<Time>, mut game_time: ResMut<GameTime>) {
     }
 }
 
-fn speed_up_game_over_time(mut game_time: ResMut<GameTime>) {
-    game_time.multiplier = 1. + game_time.elapsed / GAME_TIME_DOUBLING_TIME;
+fn speed_up_game_over_time(
+    difficulty: Res<Difficulty>,
+    practice: Res<PracticeMode>,
+    mut game_time: ResMut<GameTime>,
+) {
+    // practice mode can hold the game at a fixed speed
+    if practice.enabled {
+        if let Some(multiplier) = practice.frozen_multiplier {
+            game_time.multiplier = multiplier;
+            return;
+        }
+    }
+
+    game_time.multiplier = 1. + game_time.elapsed / difficulty.doubling_time;
 }
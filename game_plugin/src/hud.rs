@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    player::{PlayerShip, STRAIN_FLICKER_THRESHOLDS},
+    score::Score,
+    GameState, SystemLabels,
+};
+
+/// The size of the strain gauge in pixels
+const STRAIN_BAR_SIZE: (f32, f32) = (200., 16.);
+
+/// How quickly the score counter and strain gauge catch up with the real values, as the
+/// fraction of the gap closed per second
+const ROLL_RATE: f32 = 8.;
+
+/// How many captures in a row show the combo indicator
+const MIN_COMBO: u32 = 2;
+
+/// The seconds the combo indicator grows for each time the combo goes up
+const COMBO_PULSE_TIME: f32 = 0.3;
+
+const HUD_TEXT_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<HudMaterials>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_hud.system()))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        update_score_counter
+                            .system()
+                            .after(SystemLabels::UpdateScore),
+                    )
+                    .with_system(update_strain_gauge.system())
+                    .with_system(update_speed_text.system())
+                    .with_system(
+                        update_combo_indicator
+                            .system()
+                            .after(SystemLabels::UpdateScore),
+                    )
+                    .with_system(update_shield_icons.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_hud.system()),
+            );
+    }
+}
+
+/// The colors of the HUD's bars and icons
+struct HudMaterials {
+    transparent: Handle<ColorMaterial>,
+    bar_background: Handle<ColorMaterial>,
+    marker: Handle<ColorMaterial>,
+
+    /// The strain gauge's fill below, between and above the flicker thresholds
+    strain: [Handle<ColorMaterial>; 3],
+
+    shield: Handle<ColorMaterial>,
+}
+
+impl FromWorld for HudMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        HudMaterials {
+            transparent: materials.add(Color::NONE.into()),
+            bar_background: materials.add(Color::rgba(0., 0., 0., 0.3).into()),
+            marker: materials.add(Color::rgba(1., 1., 1., 0.8).into()),
+            strain: [
+                materials.add(Color::rgb(0.4, 0.7, 0.3).into()),
+                materials.add(Color::rgb(0.9, 0.7, 0.2).into()),
+                materials.add(Color::rgb(0.8, 0.2, 0.2).into()),
+            ],
+            shield: materials.add(Color::rgb(0.3, 0.5, 0.9).into()),
+        }
+    }
+}
+
+struct HudItem;
+
+/// The score, rolling up towards the real score
+struct ScoreCounter {
+    shown: f32,
+}
+
+/// The filled part of the strain gauge, easing towards the real strain
+struct StrainFill {
+    shown: f32,
+}
+
+struct StrainText;
+
+struct SpeedText;
+
+/// Shows how many obstacles have been captured in a row, growing briefly each time the
+/// combo goes up
+struct ComboIndicator {
+    combo: u32,
+    pulse: f32,
+}
+
+/// Holds an icon for each shield the tractors have left
+struct ShieldIcons {
+    shown: u32,
+}
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>, materials: Res<HudMaterials>) {
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+        font_size,
+        color: HUD_TEXT_COLOR,
+    };
+    let text = |value: &str, font_size: f32| Text {
+        sections: vec![TextSection {
+            value: value.to_string(),
+            style: text_style(font_size),
+        }],
+        alignment: Default::default(),
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.transparent.clone(),
+            ..Default::default()
+        })
+        .insert(HudItem)
+        .with_children(|hud| {
+            hud.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    padding: Rect::all(Val::Px(10.)),
+                    ..Default::default()
+                },
+                material: materials.transparent.clone(),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                row.spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(160.), Val::Auto),
+                        ..Default::default()
+                    },
+                    text: text("0", 40.),
+                    ..Default::default()
+                })
+                .insert(ScoreCounter { shown: 0. });
+
+                // the strain gauge, with markers where the tether starts flickering faster
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: materials.transparent.clone(),
+                    ..Default::default()
+                })
+                .with_children(|gauge| {
+                    gauge
+                        .spawn_bundle(TextBundle {
+                            text: text("Strain 0%", 16.),
+                            ..Default::default()
+                        })
+                        .insert(StrainText);
+
+                    gauge
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(
+                                    Val::Px(STRAIN_BAR_SIZE.0),
+                                    Val::Px(STRAIN_BAR_SIZE.1),
+                                ),
+                                ..Default::default()
+                            },
+                            material: materials.bar_background.clone(),
+                            ..Default::default()
+                        })
+                        .with_children(|bar| {
+                            bar.spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                                    ..Default::default()
+                                },
+                                material: materials.strain[0].clone(),
+                                ..Default::default()
+                            })
+                            .insert(StrainFill { shown: 0. });
+
+                            for threshold in STRAIN_FLICKER_THRESHOLDS.iter() {
+                                bar.spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        position: Rect {
+                                            left: Val::Percent(100. * threshold),
+                                            ..Default::default()
+                                        },
+                                        size: Size::new(Val::Px(2.), Val::Percent(100.)),
+                                        ..Default::default()
+                                    },
+                                    material: materials.marker.clone(),
+                                    ..Default::default()
+                                });
+                            }
+                        });
+                });
+
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(160.), Val::Auto),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::FlexEnd,
+                        ..Default::default()
+                    },
+                    material: materials.transparent.clone(),
+                    ..Default::default()
+                })
+                .with_children(|column| {
+                    column
+                        .spawn_bundle(TextBundle {
+                            text: text("x1.0 speed", 20.),
+                            ..Default::default()
+                        })
+                        .insert(SpeedText);
+
+                    column
+                        .spawn_bundle(TextBundle {
+                            text: text("", 24.),
+                            ..Default::default()
+                        })
+                        .insert(ComboIndicator {
+                            combo: 0,
+                            pulse: 0.,
+                        });
+                });
+            });
+
+            hud.spawn_bundle(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: materials.transparent.clone(),
+                ..Default::default()
+            })
+            .insert(ShieldIcons { shown: 0 });
+        });
+}
+
+/// Rolls the score counter up towards the score
+fn update_score_counter(
+    time: Res<Time>,
+    score: Res<Score>,
+    mut counters: Query<(&mut Text, &mut ScoreCounter)>,
+) {
+    let catch_up = (ROLL_RATE * time.delta_seconds()).min(1.);
+
+    for (mut text, mut counter) in counters.iter_mut() {
+        counter.shown += (score.current - counter.shown) * catch_up;
+        if (score.current - counter.shown).abs() < 1. {
+            counter.shown = score.current;
+        }
+
+        text.sections[0].value = format!("{:.0}", counter.shown.floor());
+    }
+}
+
+/// Eases the strain gauge towards the strain on the most strained tether, coloring it by
+/// which flicker thresholds it has passed
+fn update_strain_gauge(
+    time: Res<Time>,
+    ship: Res<PlayerShip>,
+    materials: Res<HudMaterials>,
+    mut fills: Query<(&mut Style, &mut Handle<ColorMaterial>, &mut StrainFill)>,
+    mut strain_text: Query<&mut Text, With<StrainText>>,
+) {
+    let strain = ship.strain_ratio().min(1.);
+    let catch_up = (ROLL_RATE * time.delta_seconds()).min(1.);
+
+    for (mut style, mut material, mut fill) in fills.iter_mut() {
+        fill.shown += (strain - fill.shown) * catch_up;
+        style.size.width = Val::Percent(100. * fill.shown);
+
+        let level = STRAIN_FLICKER_THRESHOLDS
+            .iter()
+            .filter(|threshold| fill.shown > **threshold)
+            .count();
+        if *material != materials.strain[level] {
+            *material = materials.strain[level].clone();
+        }
+    }
+
+    for mut text in strain_text.iter_mut() {
+        text.sections[0].value = format!("Strain {:.0}%", 100. * ship.strain_ratio());
+        text.sections[0].style.color = Color::rgb(0.3 + 0.5 * strain, 0.3, 0.3);
+    }
+}
+
+fn update_speed_text(time: Res<GameTime>, mut speed_text: Query<&mut Text, With<SpeedText>>) {
+    for mut text in speed_text.iter_mut() {
+        text.sections[0].value = format!("x{:.1} speed", time.multiplier);
+    }
+}
+
+/// Shows the combo once a few obstacles have been captured in a row, pulsing as it grows
+fn update_combo_indicator(
+    time: Res<Time>,
+    score: Res<Score>,
+    mut indicators: Query<(&mut Text, &mut ComboIndicator)>,
+) {
+    for (mut text, mut indicator) in indicators.iter_mut() {
+        if score.combo > indicator.combo {
+            indicator.pulse = COMBO_PULSE_TIME;
+        }
+        indicator.combo = score.combo;
+        indicator.pulse = (indicator.pulse - time.delta_seconds()).max(0.);
+
+        text.sections[0].value = if score.combo >= MIN_COMBO {
+            format!("{} in a row!", score.combo)
+        } else {
+            "".into()
+        };
+        text.sections[0].style.font_size = 24. * (1. + indicator.pulse / COMBO_PULSE_TIME / 2.);
+    }
+}
+
+/// Shows an icon for each shield left, refreshing when a shield is used
+fn update_shield_icons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ship: Res<PlayerShip>,
+    materials: Res<HudMaterials>,
+    mut icons: Query<(Entity, &mut ShieldIcons, Option<&Children>)>,
+) {
+    for (entity, mut icons, children) in icons.iter_mut() {
+        if icons.shown == ship.shields {
+            continue;
+        }
+        icons.shown = ship.shields;
+
+        for child in children.iter().flat_map(|children| children.iter()) {
+            commands.entity(*child).despawn_recursive();
+        }
+        commands.entity(entity).with_children(|row| {
+            for _ in 0..ship.shields {
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(24.), Val::Px(24.)),
+                        margin: Rect::all(Val::Px(4.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: materials.shield.clone(),
+                    ..Default::default()
+                })
+                .with_children(|icon| {
+                    icon.spawn_bundle(TextBundle {
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: "S".into(),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 18.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            }],
+                            alignment: Default::default(),
+                        },
+                        ..Default::default()
+                    });
+                });
+            }
+        });
+    }
+}
+
+fn despawn_hud(mut commands: Commands, items: Query<Entity, With<HudItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
@@ -1,27 +1,60 @@
+mod achievements;
 mod actions;
 mod audio;
 mod audio_events;
+mod camera;
+mod difficulty;
+mod director;
+mod editor;
 pub mod game_map;
 mod game_over_ui;
 mod game_time;
+mod hud;
 mod loading;
 mod menu;
 mod obstacles;
+mod particles;
 mod player;
+mod practice;
+mod profile;
+mod profile_ui;
+mod run_stats;
 mod score;
 mod scrolling_background;
+mod settings;
+mod shop;
+mod skins;
+mod storage;
+mod strain_warning;
+mod tether;
+mod tutorial;
 
+use crate::achievements::AchievementsPlugin;
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::audio_events::AudioEventsPlugin;
+use crate::camera::CameraPlugin;
+use crate::difficulty::DifficultyPlugin;
+use crate::director::DirectorPlugin;
+use crate::editor::EditorPlugin;
 use crate::game_over_ui::GameOverPlugin;
 use crate::game_time::GameTimePlugin;
+use crate::hud::HudPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 use crate::obstacles::ObstaclePlugin;
+use crate::particles::ParticlesPlugin;
 use crate::player::PlayerPlugin;
+use crate::practice::PracticePlugin;
+use crate::profile_ui::ProfileUiPlugin;
+use crate::run_stats::RunStatsPlugin;
 use crate::score::ScorePlugin;
 use crate::scrolling_background::ScrollingBackgroundPlugin;
+use crate::settings::SettingsPlugin;
+use crate::shop::ShopPlugin;
+use crate::skins::SkinsPlugin;
+use crate::strain_warning::StrainWarningPlugin;
+use crate::tutorial::TutorialPlugin;
 
 use bevy::app::AppBuilder;
 // use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
@@ -33,6 +66,11 @@ enum GameState {
     Playing,
     Menu,
     GameOver,
+    Editor,
+    Shop,
+    Achievements,
+    Profile,
+    Settings,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
@@ -42,6 +80,8 @@ enum SystemLabels {
     MoveObstacles,
     MovePlayer,
     UpdateScore,
+    UpdateCamera,
+    EditPatterns,
 }
 
 pub struct GamePlugin;
@@ -50,16 +90,31 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_state(GameState::Loading)
             .add_plugin(GameTimePlugin)
+            .add_plugin(DifficultyPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(PlayerPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(PracticePlugin)
+            .add_plugin(TutorialPlugin)
             .add_plugin(ObstaclePlugin)
+            .add_plugin(ParticlesPlugin)
+            .add_plugin(DirectorPlugin)
+            .add_plugin(EditorPlugin)
+            .add_plugin(ShopPlugin)
+            .add_plugin(SkinsPlugin)
+            .add_plugin(AchievementsPlugin)
+            .add_plugin(ProfileUiPlugin)
+            .add_plugin(SettingsPlugin)
             .add_plugin(ScorePlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(RunStatsPlugin)
             .add_plugin(GameOverPlugin)
             .add_plugin(ScrollingBackgroundPlugin)
             .add_plugin(AudioEventsPlugin)
+            .add_plugin(StrainWarningPlugin)
             // .add_plugin(FrameTimeDiagnosticsPlugin::default())
             // .add_plugin(LogDiagnosticsPlugin::default())
             ;
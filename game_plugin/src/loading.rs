@@ -1,6 +1,10 @@
-mod paths;
+pub mod paths;
 
 use crate::loading::paths::PATHS;
+use crate::obstacles::pattern_file::SpawnPatternsAsset;
+use crate::obstacles::patterns::AvailableSpawnPatterns;
+use crate::obstacles::waves::WaveScript;
+use crate::skins::{Skin, Skins};
 use crate::GameState;
 use bevy::asset::LoadState;
 use bevy::prelude::*;
@@ -21,6 +25,12 @@ pub struct LoadingState {
     textures: Vec<HandleUntyped>,
     fonts: Vec<HandleUntyped>,
     audio: Vec<HandleUntyped>,
+    spawn_patterns: Handle<SpawnPatternsAsset>,
+    wave_script: Handle<WaveScript>,
+    skins: Vec<Handle<Skin>>,
+
+    /// The textures used by the loaded skins, once the skin files have loaded
+    skin_textures: Option<Vec<HandleUntyped>>,
 }
 
 pub struct FontAssets {
@@ -30,14 +40,24 @@ pub struct FontAssets {
 pub struct AudioAssets {
     pub collect: Handle<AudioSource>,
     pub music: Handle<AudioSource>,
-    pub tether_break: Handle<AudioSource>,
+    pub music_pulse: Handle<AudioSource>,
+    pub music_tension: Handle<AudioSource>,
+    pub stinger: Handle<AudioSource>,
+    pub game_over: Handle<AudioSource>,
+    pub tractor_move: Handle<AudioSource>,
+    pub creak: Handle<AudioSource>,
+    pub snap: Handle<AudioSource>,
+    pub crash: Handle<AudioSource>,
+    pub power_up: Handle<AudioSource>,
+    pub menu_hover: Handle<AudioSource>,
+    pub menu_click: Handle<AudioSource>,
+    pub strain_warning: Handle<AudioSource>,
 }
 
 pub struct TextureAssets {
     pub cloud_001: Handle<Texture>,
     pub player_left: Handle<Texture>,
     pub player_right: Handle<Texture>,
-    pub laser: Handle<Texture>,
     pub grass: Handle<Texture>,
 }
 
@@ -55,18 +75,37 @@ fn start_loading(
     let mut audio: Vec<HandleUntyped> = vec![];
     audio.push(asset_server.load_untyped(PATHS.audio_collect));
     audio.push(asset_server.load_untyped(PATHS.audio_music));
+    audio.push(asset_server.load_untyped(PATHS.audio_music_pulse));
+    audio.push(asset_server.load_untyped(PATHS.audio_music_tension));
+    audio.push(asset_server.load_untyped(PATHS.audio_stinger));
+    audio.push(asset_server.load_untyped(PATHS.audio_game_over));
+    audio.push(asset_server.load_untyped(PATHS.audio_tractor_move));
+    audio.push(asset_server.load_untyped(PATHS.audio_creak));
+    audio.push(asset_server.load_untyped(PATHS.audio_snap));
+    audio.push(asset_server.load_untyped(PATHS.audio_crash));
+    audio.push(asset_server.load_untyped(PATHS.audio_power_up));
+    audio.push(asset_server.load_untyped(PATHS.audio_menu_hover));
+    audio.push(asset_server.load_untyped(PATHS.audio_menu_click));
+    audio.push(asset_server.load_untyped(PATHS.audio_strain_warning));
 
     let mut textures: Vec<HandleUntyped> = vec![];
     textures.push(asset_server.load_untyped(PATHS.cloud_001));
     textures.push(asset_server.load_untyped(PATHS.player_left));
     textures.push(asset_server.load_untyped(PATHS.player_right));
-    textures.push(asset_server.load_untyped(PATHS.laser));
     textures.push(asset_server.load_untyped(PATHS.grass));
 
     commands.insert_resource(LoadingState {
         textures,
         fonts,
         audio,
+        spawn_patterns: asset_server.load(PATHS.spawn_patterns),
+        wave_script: asset_server.load(PATHS.wave_script),
+        skins: PATHS
+            .skins
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+        skin_textures: None,
     });
 
     commands
@@ -105,7 +144,10 @@ fn check_state(
     mut commands: Commands,
     mut state: ResMut<State<GameState>>,
     asset_server: Res<AssetServer>,
-    loading_state: Res<LoadingState>,
+    mut loading_state: ResMut<LoadingState>,
+    spawn_patterns: Res<Assets<SpawnPatternsAsset>>,
+    wave_scripts: Res<Assets<WaveScript>>,
+    skin_assets: Res<Assets<Skin>>,
     mut loading_text: Query<&mut Text, With<LoadingText>>,
     loading_items: Query<Entity, With<LoadingItem>>,
 ) {
@@ -128,6 +170,85 @@ fn check_state(
         return;
     }
 
+    // a broken pattern file falls back to the built in patterns rather than blocking the game
+    match asset_server.get_load_state(&loading_state.spawn_patterns) {
+        LoadState::Loaded => {
+            if let Some(patterns) = spawn_patterns.get(&loading_state.spawn_patterns) {
+                commands.insert_resource::<AvailableSpawnPatterns>(patterns.0.clone());
+            }
+        }
+        LoadState::Failed => {
+            println!("Unable to load spawn patterns, using the built in patterns");
+        }
+        _ => {
+            loading_text.single_mut().unwrap().sections[0].value =
+                "Loading spawn patterns...".into();
+            return;
+        }
+    }
+
+    match asset_server.get_load_state(&loading_state.wave_script) {
+        LoadState::Loaded => {
+            if let Some(script) = wave_scripts.get(&loading_state.wave_script) {
+                commands.insert_resource(script.clone());
+            }
+        }
+        LoadState::Failed => {
+            println!("Unable to load the wave script, using the default waves");
+        }
+        _ => {
+            loading_text.single_mut().unwrap().sections[0].value = "Loading waves...".into();
+            return;
+        }
+    }
+
+    // broken skin files are skipped, falling back to the classic skin if none load
+    let skins_loading = loading_state.skins.iter().any(|handle| {
+        !matches!(
+            asset_server.get_load_state(handle),
+            LoadState::Loaded | LoadState::Failed
+        )
+    });
+    if skins_loading {
+        loading_text.single_mut().unwrap().sections[0].value = "Loading skins...".into();
+        return;
+    }
+
+    let mut skins = loading_state
+        .skins
+        .iter()
+        .filter_map(|handle| skin_assets.get(handle))
+        .cloned()
+        .collect::<Vec<_>>();
+    if skins.len() < loading_state.skins.len() {
+        println!("Unable to load some skins, they won't be available");
+    }
+    if skins.is_empty() {
+        skins.push(Skin::default());
+    }
+
+    // the skin files say which textures they need, so those load afterwards
+    if loading_state.skin_textures.is_none() {
+        loading_state.skin_textures = Some(
+            skins
+                .iter()
+                .flat_map(|skin| skin.textures().to_vec())
+                .map(|path| asset_server.load_untyped(path))
+                .collect(),
+        );
+    }
+    if let Some(skin_textures) = &loading_state.skin_textures {
+        if LoadState::Loaded
+            != asset_server.get_group_load_state(skin_textures.iter().map(|handle| handle.id))
+        {
+            loading_text.single_mut().unwrap().sections[0].value =
+                "Loading skin textures...".into();
+            return;
+        }
+    }
+
+    commands.insert_resource(Skins(skins));
+
     commands.insert_resource(FontAssets {
         fira_sans: asset_server.get_handle(PATHS.fira_sans),
     });
@@ -135,14 +256,24 @@ fn check_state(
     commands.insert_resource(AudioAssets {
         collect: asset_server.get_handle(PATHS.audio_collect),
         music: asset_server.get_handle(PATHS.audio_music),
-        tether_break: asset_server.get_handle(PATHS.audio_game_over),
+        music_pulse: asset_server.get_handle(PATHS.audio_music_pulse),
+        music_tension: asset_server.get_handle(PATHS.audio_music_tension),
+        stinger: asset_server.get_handle(PATHS.audio_stinger),
+        game_over: asset_server.get_handle(PATHS.audio_game_over),
+        tractor_move: asset_server.get_handle(PATHS.audio_tractor_move),
+        creak: asset_server.get_handle(PATHS.audio_creak),
+        snap: asset_server.get_handle(PATHS.audio_snap),
+        crash: asset_server.get_handle(PATHS.audio_crash),
+        power_up: asset_server.get_handle(PATHS.audio_power_up),
+        menu_hover: asset_server.get_handle(PATHS.audio_menu_hover),
+        menu_click: asset_server.get_handle(PATHS.audio_menu_click),
+        strain_warning: asset_server.get_handle(PATHS.audio_strain_warning),
     });
 
     commands.insert_resource(TextureAssets {
         cloud_001: asset_server.get_handle(PATHS.cloud_001),
         player_left: asset_server.get_handle(PATHS.player_left),
         player_right: asset_server.get_handle(PATHS.player_right),
-        laser: asset_server.get_handle(PATHS.laser),
         grass: asset_server.get_handle(PATHS.grass),
     });
 
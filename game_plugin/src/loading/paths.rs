@@ -3,11 +3,24 @@ pub struct AssetPaths {
     pub audio_collect: &'static str,
     pub audio_music: &'static str,
     pub audio_game_over: &'static str,
+    pub audio_music_pulse: &'static str,
+    pub audio_music_tension: &'static str,
+    pub audio_stinger: &'static str,
+    pub audio_tractor_move: &'static str,
+    pub audio_creak: &'static str,
+    pub audio_snap: &'static str,
+    pub audio_crash: &'static str,
+    pub audio_power_up: &'static str,
+    pub audio_menu_hover: &'static str,
+    pub audio_menu_click: &'static str,
+    pub audio_strain_warning: &'static str,
     pub cloud_001: &'static str,
     pub player_left: &'static str,
     pub player_right: &'static str,
-    pub laser: &'static str,
     pub grass: &'static str,
+    pub spawn_patterns: &'static str,
+    pub wave_script: &'static str,
+    pub skins: [&'static str; 4],
 }
 
 pub const PATHS: AssetPaths = AssetPaths {
@@ -15,9 +28,27 @@ pub const PATHS: AssetPaths = AssetPaths {
     audio_collect: "audio/collect.ogg",
     audio_music: "audio/music.ogg",
     audio_game_over: "audio/game_over.ogg",
+    audio_music_pulse: "audio/music_pulse.wav",
+    audio_music_tension: "audio/music_tension.wav",
+    audio_stinger: "audio/stinger.wav",
+    audio_tractor_move: "audio/tractor_move.wav",
+    audio_creak: "audio/creak.wav",
+    audio_snap: "audio/snap.wav",
+    audio_crash: "audio/crash.wav",
+    audio_power_up: "audio/power_up.wav",
+    audio_menu_hover: "audio/menu_hover.wav",
+    audio_menu_click: "audio/menu_click.wav",
+    audio_strain_warning: "audio/strain_warning.wav",
     cloud_001: "textures/cloud_001.png",
     player_left: "textures/player_left.png",
     player_right: "textures/player_right.png",
-    laser: "textures/laser.png",
     grass: "textures/grass.png",
+    spawn_patterns: "patterns/default.patterns",
+    wave_script: "waves/default.wave",
+    skins: [
+        "skins/classic.skin",
+        "skins/golden.skin",
+        "skins/midnight.skin",
+        "skins/neon.skin",
+    ],
 };
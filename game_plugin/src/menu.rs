@@ -1,4 +1,16 @@
-use crate::{actions::Actions, GameState};
+use crate::{
+    actions::Actions,
+    difficulty::Difficulty,
+    director::DifficultyDirector,
+    player::TractorCount,
+    practice::PracticeMode,
+    profile::Profile,
+    score::{mode_name, HighScores},
+    settings::Settings,
+    skins::Skins,
+    tutorial::Tutorial,
+    GameState,
+};
 use bevy::prelude::*;
 
 pub struct MenuPlugin;
@@ -15,9 +27,10 @@ impl Plugin for MenuPlugin {
     }
 }
 
-struct ButtonMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
+/// The materials for buttons in menu screens
+pub struct ButtonMaterials {
+    pub normal: Handle<ColorMaterial>,
+    pub hovered: Handle<ColorMaterial>,
 }
 
 impl FromWorld for ButtonMaterials {
@@ -30,9 +43,73 @@ impl FromWorld for ButtonMaterials {
     }
 }
 
+/// Goes back to the menu if space was hit, returning whether it did. The press is used up
+/// so the menu doesn't see it too and start a game.
+pub fn return_to_menu(actions: &mut Actions, state: &mut State<GameState>) -> bool {
+    if !actions.restart_requested {
+        return false;
+    }
+
+    state.set(GameState::Menu).unwrap();
+    actions.restart_requested = false;
+    true
+}
+
 struct MenuItem;
 
-struct PlayButton;
+struct HighScoreText;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MenuButton {
+    Play,
+    Practice,
+    Tutorial,
+    ToggleAdaptive,
+    Difficulty,
+    Tractors,
+    Shop,
+    Skin,
+    Achievements,
+    Profile,
+    Settings,
+    Editor,
+}
+
+/// The settings shown on the menu's toggle buttons
+struct MenuSettings<'a> {
+    director: &'a DifficultyDirector,
+    difficulty: &'a Difficulty,
+    tractor_count: &'a TractorCount,
+    skin: &'a str,
+}
+
+impl MenuButton {
+    fn label(&self, settings: &MenuSettings) -> String {
+        let MenuSettings {
+            director,
+            difficulty,
+            tractor_count,
+            skin,
+        } = settings;
+
+        match self {
+            MenuButton::Play => "Play".into(),
+            MenuButton::Practice => "Practice".into(),
+            MenuButton::Tutorial => "Tutorial".into(),
+            MenuButton::ToggleAdaptive => {
+                format!("Adaptive: {}", if director.enabled { "On" } else { "Off" })
+            }
+            MenuButton::Difficulty => difficulty.preset.name().into(),
+            MenuButton::Tractors => format!("Tractors: {}", tractor_count.0),
+            MenuButton::Shop => "Shop".into(),
+            MenuButton::Skin => format!("Skin: {}", skin),
+            MenuButton::Achievements => "Achievements".into(),
+            MenuButton::Profile => "Profile".into(),
+            MenuButton::Settings => "Settings".into(),
+            MenuButton::Editor => "Editor".into(),
+        }
+    }
+}
 
 fn spawn_ui_camera(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
@@ -42,7 +119,21 @@ fn setup_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     button_materials: Res<ButtonMaterials>,
+    director: Res<DifficultyDirector>,
+    difficulty: Res<Difficulty>,
+    tractor_count: Res<TractorCount>,
+    high_scores: Res<HighScores>,
+    skins: Res<Skins>,
+    profile: Res<Profile>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
+    let settings = MenuSettings {
+        director: &director,
+        difficulty: &difficulty,
+        tractor_count: &tractor_count,
+        skin: &skins.selected(&profile).name,
+    };
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -166,9 +257,26 @@ fn setup_menu(
                 },
                 text: Text {
                     sections: vec![TextSection {
-                        value:
-                            "Use A/D to move the left tractor, and J/L to move the right tractor."
-                                .to_string(),
+                        value: "Use WASD to drive the left tractor, IJKL the next one, \
+                                and the arrow keys the third."
+                            .to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            node.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Q snaps the tractors together and E snaps them apart, \
+                                but snapping too often strains the tether."
+                            .to_string(),
                         style: TextStyle {
                             font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
                             font_size: 20.0,
@@ -192,7 +300,9 @@ fn setup_menu(
                 },
                 text: Text {
                     sections: vec![TextSection {
-                        value: "Hit the space bar or play below to start.".to_string(),
+                        value:
+                            "Hit the space bar or play below to start, or practice without failing."
+                                .to_string(),
                         style: TextStyle {
                             font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
                             font_size: 20.0,
@@ -204,38 +314,151 @@ fn setup_menu(
                 ..Default::default()
             });
 
-            node.spawn_bundle(ButtonBundle {
+            node.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: materials.add(Color::NONE.into()),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                for button in [MenuButton::Play, MenuButton::Practice, MenuButton::Tutorial].iter()
+                {
+                    spawn_menu_button(row, &asset_server, &button_materials, &settings, *button);
+                }
+            });
+
+            node.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: materials.add(Color::NONE.into()),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                for button in [
+                    MenuButton::Difficulty,
+                    MenuButton::ToggleAdaptive,
+                    MenuButton::Tractors,
+                ]
+                .iter()
+                {
+                    spawn_menu_button(row, &asset_server, &button_materials, &settings, *button);
+                }
+            });
+
+            node.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: materials.add(Color::NONE.into()),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                for button in [MenuButton::Shop, MenuButton::Skin, MenuButton::Achievements].iter()
+                {
+                    spawn_menu_button(row, &asset_server, &button_materials, &settings, *button);
+                }
+            });
+
+            node.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: materials.add(Color::NONE.into()),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                for button in [
+                    MenuButton::Profile,
+                    MenuButton::Settings,
+                    MenuButton::Editor,
+                ]
+                .iter()
+                {
+                    spawn_menu_button(row, &asset_server, &button_materials, &settings, *button);
+                }
+            });
+
+            node.spawn_bundle(TextBundle {
                 style: Style {
-                    size: Size::new(Val::Px(120.0), Val::Px(50.0)),
                     margin: Rect {
                         left: Val::Px(0.),
                         right: Val::Px(0.),
-                        top: Val::Px(20.),
+                        top: Val::Px(10.),
                         bottom: Val::Px(0.),
                     },
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
                     ..Default::default()
                 },
-                material: button_materials.normal.clone(),
+                text: Text {
+                    sections: vec![TextSection {
+                        value: high_score_label(&high_scores, &difficulty, &tractor_count),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
                 ..Default::default()
             })
-            .insert(PlayButton)
-            .with_children(|parent| {
-                parent.spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: "Play".to_string(),
-                            style: TextStyle {
-                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
-                                font_size: 40.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                });
+            .insert(HighScoreText);
+        });
+}
+
+/// Spawns a single menu button with its label
+fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    button_materials: &ButtonMaterials,
+    settings: &MenuSettings,
+    button: MenuButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                margin: Rect {
+                    left: Val::Px(10.),
+                    right: Val::Px(10.),
+                    top: Val::Px(20.),
+                    bottom: Val::Px(0.),
+                },
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: button.label(settings),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
             });
         });
 }
@@ -245,23 +468,113 @@ type ButtonInteraction<'a> = (
     &'a Interaction,
     &'a mut Handle<ColorMaterial>,
     &'a Children,
+    &'a MenuButton,
 );
 
 fn click_play_button(
     actions: Res<Actions>,
     button_materials: Res<ButtonMaterials>,
+    high_scores: Res<HighScores>,
+    mut practice: ResMut<PracticeMode>,
+    mut tutorial: ResMut<Tutorial>,
+    mut director: ResMut<DifficultyDirector>,
+    mut difficulty: ResMut<Difficulty>,
+    mut settings: ResMut<Settings>,
+    mut tractor_count: ResMut<TractorCount>,
+    mut profile: ResMut<Profile>,
+    skins: Res<Skins>,
     mut state: ResMut<State<GameState>>,
     mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
+    mut button_text: Query<&mut Text, Without<HighScoreText>>,
+    mut high_score_text: Query<&mut Text, With<HighScoreText>>,
 ) {
     if actions.restart_requested {
+        *practice = PracticeMode::default();
+        *tutorial = Tutorial::default();
         state.set(GameState::Playing).unwrap();
         return;
     }
 
-    for (_, interaction, mut material, _) in interaction_query.iter_mut() {
+    for (_, interaction, mut material, children, button) in interaction_query.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
-                state.set(GameState::Playing).unwrap();
+                match *button {
+                    MenuButton::Play | MenuButton::Practice | MenuButton::Tutorial => {
+                        let is_practice = *button == MenuButton::Practice;
+                        let is_tutorial = *button == MenuButton::Tutorial;
+
+                        // the tutorial is a practice run at a fixed speed
+                        *practice = PracticeMode {
+                            enabled: is_practice || is_tutorial,
+                            frozen_multiplier: if is_tutorial { Some(1.) } else { None },
+                            ..Default::default()
+                        };
+                        *tutorial = Tutorial {
+                            enabled: is_tutorial,
+                            ..Default::default()
+                        };
+                        state.set(GameState::Playing).unwrap();
+                        return;
+                    }
+                    MenuButton::Shop => {
+                        state.set(GameState::Shop).unwrap();
+                        return;
+                    }
+                    MenuButton::Achievements => {
+                        state.set(GameState::Achievements).unwrap();
+                        return;
+                    }
+                    MenuButton::Profile => {
+                        state.set(GameState::Profile).unwrap();
+                        return;
+                    }
+                    MenuButton::Settings => {
+                        state.set(GameState::Settings).unwrap();
+                        return;
+                    }
+                    MenuButton::Editor => {
+                        state.set(GameState::Editor).unwrap();
+                        return;
+                    }
+                    MenuButton::ToggleAdaptive => {
+                        director.enabled = !director.enabled;
+                    }
+                    MenuButton::Tractors => {
+                        *tractor_count = tractor_count.next();
+
+                        for mut text in high_score_text.iter_mut() {
+                            text.sections[0].value =
+                                high_score_label(&high_scores, &difficulty, &tractor_count);
+                        }
+                    }
+                    MenuButton::Skin => {
+                        profile.skin = skins.next(&profile).name.clone();
+                        profile.save();
+                    }
+                    MenuButton::Difficulty => {
+                        *difficulty = difficulty.preset.next().settings();
+                        settings.difficulty = difficulty.preset;
+                        settings.save();
+
+                        for mut text in high_score_text.iter_mut() {
+                            text.sections[0].value =
+                                high_score_label(&high_scores, &difficulty, &tractor_count);
+                        }
+                    }
+                }
+
+                // refresh the label of toggle buttons
+                let menu_settings = MenuSettings {
+                    director: &director,
+                    difficulty: &difficulty,
+                    tractor_count: &tractor_count,
+                    skin: &skins.selected(&profile).name,
+                };
+                for child in children.iter() {
+                    if let Ok(mut text) = button_text.get_mut(*child) {
+                        text.sections[0].value = button.label(&menu_settings);
+                    }
+                }
             }
             Interaction::Hovered => {
                 *material = button_materials.hovered.clone();
@@ -273,6 +586,20 @@ fn click_play_button(
     }
 }
 
+/// The high score line shown for the selected difficulty and tractor count
+fn high_score_label(
+    high_scores: &HighScores,
+    difficulty: &Difficulty,
+    tractor_count: &TractorCount,
+) -> String {
+    let mode = (difficulty.preset, tractor_count.0);
+    format!(
+        "{} high score: {:.0}",
+        mode_name(mode),
+        high_scores.get(mode)
+    )
+}
+
 fn despawn_menu(mut commands: Commands, items: Query<Entity, With<MenuItem>>) {
     for item in items.iter() {
         commands.entity(item).despawn_recursive();
@@ -1,87 +1,74 @@
+pub mod pattern_file;
+pub mod patterns;
+pub mod validation;
+pub mod waves;
+
 use bevy::prelude::*;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::{thread_rng, Rng};
 
 use crate::{
+    audio::MusicIntensity,
     audio_events::{AudioEffect, PlayAudioEffectEvent},
-    by_side,
+    difficulty::Difficulty,
+    director::DifficultyDirector,
     game_map::GameMap,
     game_time::GameTime,
     loading::TextureAssets,
-    player::{IsDead, Player, PlayerShip, PlayerShipSide},
+    obstacles::{
+        pattern_file::{SpawnPatternsAsset, SpawnPatternsLoader},
+        patterns::{AvailableSpawnPatterns, SpawnChoice, SpawnHistory, SpawnPattern},
+        validation::{validate_spawn_patterns, SolvabilityParams},
+        waves::{
+            WaveInstruction, WavePosition, WaveRunner, WaveScript, WaveScriptLoader, WaveValue,
+        },
+    },
+    player::{
+        tractor_positions, DeathCause, IsDead, Player, PlayerShip, PlayerShipSide, MAX_TRACTORS,
+    },
+    practice::PracticeMode,
     score::{CapturedObstacle, Score},
+    tether::Tethers,
+    tutorial::Tutorial,
     GameState, SystemLabels,
 };
 
-/// Possible spawn patterns for obstacles, specified as sprite sized offsets from the main
-#[derive(Clone)]
-pub struct SpawnPattern {
-    pub offsets: Vec<Vec2>,
-    pub min_score: f32,
-}
+pub struct Obstacle;
 
-pub struct AvailableSpawnPatterns {
-    pub patterns: Vec<SpawnPattern>,
+/// What happened to an obstacle as it crossed the tether line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleOutcome {
+    Captured,
+    Missed,
+    NearMiss,
+    HitTractor,
 }
 
-impl AvailableSpawnPatterns {
-    fn new() -> Self {
-        AvailableSpawnPatterns {
-            patterns: vec![
-                SpawnPattern {
-                    offsets: vec![Vec2::ZERO],
-                    min_score: -1.,
-                },
-                SpawnPattern {
-                    offsets: vec![Vec2::new(-1., 0.), Vec2::ZERO, Vec2::new(1., 0.)],
-                    min_score: -1.,
-                },
-                SpawnPattern {
-                    offsets: vec![
-                        Vec2::new(-2., 0.),
-                        Vec2::new(-1., 0.),
-                        Vec2::ZERO,
-                        Vec2::new(1., 0.),
-                        Vec2::new(2., 0.),
-                    ],
-                    min_score: 50.,
-                },
-                SpawnPattern {
-                    offsets: vec![
-                        Vec2::new(-2., 0.),
-                        Vec2::ZERO,
-                        Vec2::new(1., 0.),
-                        Vec2::new(2., 0.),
-                    ],
-                    min_score: 60.,
-                },
-                SpawnPattern {
-                    offsets: vec![Vec2::new(-2., -2.), Vec2::ZERO, Vec2::new(2., 2.)],
-                    min_score: 30.,
-                },
-                SpawnPattern {
-                    offsets: vec![
-                        Vec2::new(-4., 0.),
-                        Vec2::new(-2., 0.),
-                        Vec2::ZERO,
-                        Vec2::new(2., 0.),
-                        Vec2::new(4., 0.),
-                    ],
-                    min_score: 100.,
-                },
-            ],
-        }
-    }
+/// Sent whenever an obstacle crosses the tether line
+pub struct ObstacleCrossedEvent {
+    pub outcome: ObstacleOutcome,
+    pub position: Vec2,
 }
 
-pub struct Obstacle;
+/// The default speed obstacles fall at, in world units per second
+pub const OBSTACLE_FALL_SPEED: f32 = 150.;
 
-pub struct SpawnTimer;
+/// The default time between obstacle spawns, in seconds, see Difficulty for the active value
+pub const SPAWN_INTERVAL: f32 = 3.;
 
 pub struct ObstaclePlugin;
 
 impl Plugin for ObstaclePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(AvailableSpawnPatterns::new())
+            .add_asset::<SpawnPatternsAsset>()
+            .init_asset_loader::<SpawnPatternsLoader>()
+            .init_resource::<WaveScript>()
+            .add_asset::<WaveScript>()
+            .init_asset_loader::<WaveScriptLoader>()
+            .add_event::<ObstacleCrossedEvent>()
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(check_spawn_patterns.system()),
+            )
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing)
                     .with_system(setup_obstacle_spawning.system()),
@@ -112,15 +99,51 @@ impl Plugin for ObstaclePlugin {
     }
 }
 
-/// Starts the obstacle spawn timer
+/// The game speed multiplier spawn patterns are checked at once they have loaded
+pub const VALIDATION_MULTIPLIER: f32 = 2.;
+
+/// Warns about spawn patterns that can't be avoided or captured, or that spawn off the map
+fn check_spawn_patterns(
+    game_map: Res<GameMap>,
+    difficulty: Res<Difficulty>,
+    patterns: Res<AvailableSpawnPatterns>,
+) {
+    for tractors in 2..=MAX_TRACTORS {
+        let params =
+            SolvabilityParams::new(&game_map, &difficulty, VALIDATION_MULTIPLIER, tractors);
+
+        for issue in validate_spawn_patterns(&patterns, &game_map, &params) {
+            println!(
+                "Spawn pattern issue: {:?} at x = {} is {:?} with {} tractors",
+                issue.choice, issue.spawn_x, issue.kind, issue.tractors
+            );
+        }
+    }
+}
+
+/// A spawn sequence that is part way through spawning its steps
+struct RunningSequence {
+    sequence: usize,
+    next_step: usize,
+    time_to_next_step: f32,
+    spawn_x: f32,
+}
+
+/// Tracks what has been spawned recently, any sequence currently being spawned and
+/// where the wave script is up to
+#[derive(Default)]
+pub struct SpawnState {
+    history: SpawnHistory,
+    running_sequence: Option<RunningSequence>,
+    wave: WaveRunner,
+}
+
+/// Starts the wave script from the beginning
 fn setup_obstacle_spawning(mut commands: Commands) {
-    commands
-        .spawn()
-        .insert(SpawnTimer)
-        .insert(Timer::from_seconds(3., true));
+    commands.insert_resource(SpawnState::default());
 }
 
-/// Spawns obstacles at the top of the screen
+/// Runs the wave script, which spawns obstacles at the top of the screen
 fn spawn_obstacles(
     mut commands: Commands,
     time: Res<GameTime>,
@@ -128,37 +151,174 @@ fn spawn_obstacles(
     textures: Res<TextureAssets>,
     game_map: Res<GameMap>,
     patterns: Res<AvailableSpawnPatterns>,
+    script: Res<WaveScript>,
     score: Res<Score>,
+    practice: Res<PracticeMode>,
+    tutorial: Res<Tutorial>,
+    director: Res<DifficultyDirector>,
+    difficulty: Res<Difficulty>,
+    mut music_intensity: ResMut<MusicIntensity>,
+    mut spawn_state: ResMut<SpawnState>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut timers: Query<&mut Timer, With<SpawnTimer>>,
 ) {
-    if ship.is_dead {
+    // the tutorial spawns its own obstacles
+    if ship.is_dead || tutorial.enabled {
         return;
     }
 
-    let mut timer = timers.single_mut().unwrap();
-    timer.tick(time.delta_duration);
-    if !timer.just_finished() {
+    let SpawnState {
+        history,
+        running_sequence,
+        wave,
+    } = &mut *spawn_state;
+
+    // finish spawning a sequence before the script carries on
+    if let Some(running) = running_sequence.as_mut() {
+        let steps = &patterns.sequences[running.sequence].steps;
+        running.time_to_next_step -= time.delta;
+
+        while running.time_to_next_step <= 0. && running.next_step < steps.len() {
+            let step = &steps[running.next_step];
+            spawn_pattern_obstacles(
+                &mut commands,
+                &textures,
+                &game_map,
+                &mut texture_atlases,
+                running.spawn_x + step.x_offset * game_map.sprite_size,
+                &patterns.patterns[step.pattern],
+            );
+
+            running.next_step += 1;
+            if let Some(next) = steps.get(running.next_step) {
+                running.time_to_next_step += next.delay;
+            }
+        }
+
+        if running.next_step >= steps.len() {
+            *running_sequence = None;
+        }
+
         return;
     }
 
     let mut rng = thread_rng();
-    let x_extents = -(game_map.width / 2.)..=(game_map.width / 2.);
-
-    let spawn_x = rng.gen_range(x_extents).floor() * game_map.sprite_size;
-    let spawn_patterns = patterns
-        .patterns
-        .iter()
-        .filter_map(|pattern| {
-            if pattern.min_score < score.current {
-                Some(pattern.clone())
-            } else {
-                None
+    let spawn_interval = difficulty.spawn_interval * director.spawn_interval_scale();
+    let value = |value| match value {
+        WaveValue::Score => score.current,
+        WaveValue::Time => time.elapsed,
+    };
+
+    wave.run(
+        &script,
+        time.delta,
+        spawn_interval,
+        value,
+        |wave, instruction| {
+            match instruction {
+                WaveInstruction::Position(WavePosition::Random) => {
+                    let x_extents = -(game_map.width / 2.)..=(game_map.width / 2.);
+                    wave.spawn_x = rng.gen_range(x_extents).floor() * game_map.sprite_size;
+                }
+                WaveInstruction::Position(WavePosition::Fixed(x)) => {
+                    wave.spawn_x = x * game_map.sprite_size;
+                }
+                WaveInstruction::Spawn(offsets) => {
+                    spawn_pattern_obstacles(
+                        &mut commands,
+                        &textures,
+                        &game_map,
+                        &mut texture_atlases,
+                        wave.spawn_x,
+                        &SpawnPattern {
+                            offsets: offsets.clone(),
+                            ..Default::default()
+                        },
+                    );
+                }
+                WaveInstruction::Pattern(idx) => match patterns.patterns.get(*idx) {
+                    Some(pattern) => spawn_pattern_obstacles(
+                        &mut commands,
+                        &textures,
+                        &game_map,
+                        &mut texture_atlases,
+                        wave.spawn_x,
+                        pattern,
+                    ),
+                    None => println!("Wave script spawns missing pattern {}", idx),
+                },
+                WaveInstruction::Random => {
+                    // practice mode can force a single pattern regardless of score
+                    let forced_pattern = if practice.enabled {
+                        practice
+                            .forced_pattern
+                            .filter(|idx| *idx < patterns.patterns.len())
+                    } else {
+                        None
+                    };
+
+                    let choice = match forced_pattern {
+                        Some(idx) => SpawnChoice::Pattern(idx),
+                        None => match patterns.choose(
+                            score.current,
+                            history,
+                            |pattern| director.pattern_weight(pattern),
+                            &mut rng,
+                        ) {
+                            Some(choice) => choice,
+                            None => return true,
+                        },
+                    };
+
+                    history.record(choice);
+
+                    match choice {
+                        SpawnChoice::Pattern(idx) => {
+                            spawn_pattern_obstacles(
+                                &mut commands,
+                                &textures,
+                                &game_map,
+                                &mut texture_atlases,
+                                wave.spawn_x,
+                                &patterns.patterns[idx],
+                            );
+                        }
+                        SpawnChoice::Sequence(idx) => {
+                            // the first step is spawned next frame, after its own delay, and
+                            // the script waits for the sequence to finish
+                            *running_sequence = Some(RunningSequence {
+                                sequence: idx,
+                                next_step: 0,
+                                time_to_next_step: patterns.sequences[idx]
+                                    .steps
+                                    .first()
+                                    .map(|step| step.delay)
+                                    .unwrap_or(0.),
+                                spawn_x: wave.spawn_x,
+                            });
+                            return false;
+                        }
+                    }
+                }
+                WaveInstruction::Music(intensity) => {
+                    music_intensity.0 = *intensity;
+                }
+                _ => {}
             }
-        })
-        .collect::<Vec<_>>();
-    let spawn_pattern = spawn_patterns.choose(&mut rng).unwrap();
+            true
+        },
+    );
+}
 
+/// Spawns the obstacles for a single spawn pattern above the top of the screen,
+/// centered on spawn_x
+pub fn spawn_pattern_obstacles(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    game_map: &GameMap,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    spawn_x: f32,
+    spawn_pattern: &SpawnPattern,
+) {
     println!("Spawning obstacle");
     let texture_atlas =
         TextureAtlas::from_grid(textures.cloud_001.clone(), Vec2::new(32., 32.0), 4, 1);
@@ -188,7 +348,12 @@ fn move_obstacles(
     time: Res<GameTime>,
     mut ship: ResMut<PlayerShip>,
     game_map: Res<GameMap>,
+    mut practice: ResMut<PracticeMode>,
+    director: Res<DifficultyDirector>,
+    difficulty: Res<Difficulty>,
     mut audio_events: EventWriter<PlayAudioEffectEvent>,
+    mut crossed_events: EventWriter<ObstacleCrossedEvent>,
+    tethers: Option<Res<Tethers>>,
     players: Query<Entity, (With<Player>, Without<IsDead>)>,
     ship_sides: Query<(&Transform, &PlayerShipSide), Without<Player>>,
     mut obstacles: Query<
@@ -206,11 +371,16 @@ fn move_obstacles(
         return;
     }
 
-    let by = game_map.bottom_y();
-    let sides = ship_sides.iter().fold((0., 0.), |acc, (tx, side)| {
-        by_side!(side, (tx.translation.x, acc.1), (acc.0, tx.translation.x))
-    });
-    let min_x_sep = 0.8 * game_map.sprite_size;
+    // the tether line runs through every tractor in side order, sagging between them
+    let sides = tractor_positions(
+        ship_sides
+            .iter()
+            .map(|(tx, side)| (side.0, tx.translation.truncate())),
+    );
+    let (left_x, right_x) = (sides[0].x, sides[sides.len() - 1].x);
+    let min_x_sep = difficulty.obstacle_collision_distance * game_map.sprite_size;
+    let near_miss_x_sep = 1.5 * game_map.sprite_size;
+    let fall_speed = OBSTACLE_FALL_SPEED * director.fall_speed_scale();
 
     for (entity, mut tx, mut vis, mut timer, mut sprite) in obstacles.iter_mut() {
         // update the sprite
@@ -220,34 +390,103 @@ fn move_obstacles(
         }
 
         let before = tx.translation.y;
-        let after = tx.translation.y - 150. * time.delta;
+        let after = tx.translation.y - fall_speed * time.delta;
         tx.translation.y = after;
 
         // only check visible obstacles to see if they just crossed the tether line
-        if vis.is_visible && before > by && after < by {
-            let obs_x = tx.translation.x;
+        let obs_x = tx.translation.x;
+        let line_y = tethers
+            .as_ref()
+            .and_then(|tethers| tethers.0.iter().find_map(|tether| tether.y_at(obs_x)))
+            .unwrap_or_else(|| tether_line_y(&sides, obs_x));
+        if vis.is_visible && before > line_y && after < line_y {
+            let position = Vec2::new(obs_x, line_y);
+            let closest_x_sep = sides
+                .iter()
+                .map(|side| (obs_x - side.x).abs())
+                .fold(f32::MAX, f32::min);
 
             // crossed over! Check if we collided with player ships or went through the tether
-            if (obs_x - sides.0).abs() < min_x_sep || (obs_x - sides.1).abs() < min_x_sep {
+            if closest_x_sep < min_x_sep {
                 println!("Hit tractor!");
+                audio_events.send(PlayAudioEffectEvent::at(AudioEffect::Crash, obs_x));
+                crossed_events.send(ObstacleCrossedEvent {
+                    outcome: ObstacleOutcome::HitTractor,
+                    position,
+                });
+
+                // in practice mode just record the hit and carry on
+                if practice.enabled {
+                    practice.obstacle_collisions += 1;
+                    vis.is_visible = false;
+                    continue;
+                }
+
+                // a shield takes the hit instead of the tractor
+                if ship.shields > 0 {
+                    println!("Shield absorbed the hit!");
+                    // shields are the tractors' power-up, so they chime when used
+                    audio_events.send(PlayAudioEffectEvent::at(AudioEffect::PowerUp, obs_x));
+                    ship.shields -= 1;
+                    vis.is_visible = false;
+                    continue;
+                }
+
                 commands
                     .entity(players.single().unwrap())
-                    .insert(IsDead("A tractor hit an obstacle!".into()));
+                    .insert(IsDead(DeathCause::HitObstacle, position));
                 ship.is_dead = true;
                 return;
             }
 
             // otherwise check if we went through the tether
-            if obs_x > sides.0 && obs_x < sides.1 {
+            if obs_x > left_x && obs_x < right_x {
                 println!("Hit tether!");
                 vis.is_visible = false;
                 commands.entity(entity).insert(CapturedObstacle);
-                audio_events.send(PlayAudioEffectEvent(AudioEffect::Collect));
+                audio_events.send(PlayAudioEffectEvent::at(AudioEffect::Collect, obs_x));
+                crossed_events.send(ObstacleCrossedEvent {
+                    outcome: ObstacleOutcome::Captured,
+                    position,
+                });
+            } else {
+                crossed_events.send(ObstacleCrossedEvent {
+                    outcome: if closest_x_sep < near_miss_x_sep {
+                        ObstacleOutcome::NearMiss
+                    } else {
+                        ObstacleOutcome::Missed
+                    },
+                    position,
+                });
             }
         }
     }
 }
 
+/// The height of the tether line at x, given the tractor positions ordered by side.
+/// The line slopes between neighbouring tractors, and carries on flat past the outermost
+/// ones so every obstacle crosses it once. Between the tractors the sagging tethers are
+/// used instead, when they have been simulated.
+fn tether_line_y(sides: &[Vec2], x: f32) -> f32 {
+    let (first, last) = (sides[0], sides[sides.len() - 1]);
+    if x <= first.x {
+        return first.y;
+    }
+
+    sides
+        .windows(2)
+        .find(|pair| x <= pair[1].x)
+        .map(|pair| {
+            let (left, right) = (pair[0], pair[1]);
+            if right.x - left.x < f32::EPSILON {
+                right.y
+            } else {
+                left.y + (right.y - left.y) * (x - left.x) / (right.x - left.x)
+            }
+        })
+        .unwrap_or(last.y)
+}
+
 /// removes dead obstacles that are off the map
 fn remove_dead_obstacles(
     game_map: Res<GameMap>,
@@ -264,16 +503,8 @@ fn remove_dead_obstacles(
     }
 }
 
-/// despawns all obstacles and the spawn timer
-fn despawn_obstacles(
-    mut commands: Commands,
-    obstacles: Query<Entity, With<Obstacle>>,
-    spawn_timers: Query<Entity, With<SpawnTimer>>,
-) {
-    for timer in spawn_timers.iter() {
-        commands.entity(timer).despawn();
-    }
-
+/// despawns all obstacles
+fn despawn_obstacles(mut commands: Commands, obstacles: Query<Entity, With<Obstacle>>) {
     for obstacle in obstacles.iter() {
         commands.entity(obstacle).despawn();
     }
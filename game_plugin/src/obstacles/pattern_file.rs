@@ -0,0 +1,310 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    math::Vec2,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::obstacles::patterns::{
+    AvailableSpawnPatterns, SequenceStep, SpawnPattern, SpawnSequence,
+};
+
+/// Spawn patterns loaded from a `.patterns` file. The file has one pattern or sequence
+/// per line, and `#` starts a comment:
+///
+/// ```text
+/// pattern <min_score> <max_score> <weight> <cooldown> <x>,<y> <x>,<y> ...
+/// sequence <min_score> <max_score> <weight> <cooldown> <pattern>@<delay>:<x_offset> ...
+/// ```
+///
+/// Offsets are in sprites, and sequence steps refer to patterns by their order in the file.
+#[derive(Debug, TypeUuid)]
+#[uuid = "6a0b7d1e-2f43-4c8e-9b5a-3d1f0c6e8a27"]
+pub struct SpawnPatternsAsset(pub AvailableSpawnPatterns);
+
+#[derive(Default)]
+pub struct SpawnPatternsLoader;
+
+impl AssetLoader for SpawnPatternsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let contents = std::str::from_utf8(bytes)?;
+            let patterns = parse_spawn_patterns(contents).map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(SpawnPatternsAsset(patterns)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["patterns"]
+    }
+}
+
+/// Parses the contents of a `.patterns` file
+pub fn parse_spawn_patterns(contents: &str) -> Result<AvailableSpawnPatterns, String> {
+    let mut patterns = vec![];
+    let mut sequences = vec![];
+
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let kind = parts.next().unwrap_or("");
+        let parsed = match kind {
+            "pattern" => parse_pattern(&mut parts).map(|pattern| patterns.push(pattern)),
+            "sequence" => parse_sequence(&mut parts).map(|sequence| sequences.push(sequence)),
+            _ => Err(format!("unknown entry '{}'", kind)),
+        };
+
+        if let Err(e) = parsed {
+            return Err(format!("line {}: {}", line_idx + 1, e));
+        }
+    }
+
+    for sequence in sequences.iter() {
+        if let Some(step) = sequence
+            .steps
+            .iter()
+            .find(|step| step.pattern >= patterns.len())
+        {
+            return Err(format!(
+                "sequence step refers to missing pattern {}",
+                step.pattern
+            ));
+        }
+    }
+
+    Ok(AvailableSpawnPatterns {
+        patterns,
+        sequences,
+    })
+}
+
+/// Writes spawn patterns in the `.patterns` file format
+pub fn write_spawn_patterns(patterns: &AvailableSpawnPatterns) -> String {
+    let mut contents = String::from(
+        "# pattern <min_score> <max_score> <weight> <cooldown> <x>,<y> ...\n\
+         # sequence <min_score> <max_score> <weight> <cooldown> <pattern>@<delay>:<x_offset> ...\n",
+    );
+
+    for pattern in patterns.patterns.iter() {
+        contents.push_str(&format!(
+            "pattern {} {} {} {}",
+            pattern.min_score, pattern.max_score, pattern.weight, pattern.cooldown
+        ));
+        for offset in pattern.offsets.iter() {
+            contents.push_str(&format!(" {},{}", offset.x, offset.y));
+        }
+        contents.push('\n');
+    }
+
+    for sequence in patterns.sequences.iter() {
+        contents.push_str(&format!(
+            "sequence {} {} {} {}",
+            sequence.min_score, sequence.max_score, sequence.weight, sequence.cooldown
+        ));
+        for step in sequence.steps.iter() {
+            contents.push_str(&format!(
+                " {}@{}:{}",
+                step.pattern, step.delay, step.x_offset
+            ));
+        }
+        contents.push('\n');
+    }
+
+    contents
+}
+
+/// The min score, max score, weight and cooldown shared by patterns and sequences
+fn parse_header<'a, I: Iterator<Item = &'a str>>(
+    parts: &mut I,
+) -> Result<(f32, f32, f32, u32), String> {
+    let min_score = parse_value(parts.next(), "min_score")?;
+    let max_score = parse_value(parts.next(), "max_score")?;
+    let weight = parse_value(parts.next(), "weight")?;
+    let cooldown = parse_value(parts.next(), "cooldown")?;
+    Ok((min_score, max_score, weight, cooldown))
+}
+
+fn parse_pattern<'a, I: Iterator<Item = &'a str>>(parts: &mut I) -> Result<SpawnPattern, String> {
+    let (min_score, max_score, weight, cooldown) = parse_header(parts)?;
+
+    let offsets = parts
+        .map(parse_offset)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if offsets.is_empty() {
+        return Err("pattern has no offsets".into());
+    }
+
+    Ok(SpawnPattern {
+        offsets,
+        min_score,
+        max_score,
+        weight,
+        cooldown,
+    })
+}
+
+fn parse_sequence<'a, I: Iterator<Item = &'a str>>(parts: &mut I) -> Result<SpawnSequence, String> {
+    let (min_score, max_score, weight, cooldown) = parse_header(parts)?;
+
+    let steps = parts
+        .map(|step| {
+            let mut pattern_rest = step.splitn(2, '@');
+            let pattern = parse_value(pattern_rest.next(), "step pattern")?;
+            let mut delay_offset = pattern_rest.next().unwrap_or("").splitn(2, ':');
+            let delay = parse_value(delay_offset.next(), "step delay")?;
+            let x_offset = parse_value(delay_offset.next(), "step x offset")?;
+            Ok(SequenceStep {
+                pattern,
+                delay,
+                x_offset,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if steps.is_empty() {
+        return Err("sequence has no steps".into());
+    }
+
+    Ok(SpawnSequence {
+        steps,
+        min_score,
+        max_score,
+        weight,
+        cooldown,
+    })
+}
+
+/// Parses a sprite sized `<x>,<y>` offset
+pub fn parse_offset(offset: &str) -> Result<Vec2, String> {
+    let mut xy = offset.splitn(2, ',');
+    let x = parse_value(xy.next(), "offset x")?;
+    let y = parse_value(xy.next(), "offset y")?;
+    Ok(Vec2::new(x, y))
+}
+
+/// Parses a single value, naming it in the error if it is missing or invalid
+pub fn parse_value<T: std::str::FromStr>(value: Option<&str>, name: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing {}", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: &str = "
+# a comment
+pattern -1 150 1 0 0,0
+pattern 30 inf 0.6 2 -2,-2 0,0 2.5,2  # trailing comment
+
+sequence 40 inf 0.5 3 1@0:-4 0@0.8:4
+";
+
+    fn error(contents: &str) -> String {
+        parse_spawn_patterns(contents).unwrap_err()
+    }
+
+    #[test]
+    fn parses_patterns_and_sequences() {
+        let patterns = parse_spawn_patterns(PATTERNS).unwrap();
+        assert_eq!(patterns.patterns.len(), 2);
+        assert_eq!(patterns.sequences.len(), 1);
+
+        let pattern = &patterns.patterns[1];
+        assert_eq!(pattern.min_score, 30.);
+        assert_eq!(pattern.max_score, f32::INFINITY);
+        assert_eq!(pattern.weight, 0.6);
+        assert_eq!(pattern.cooldown, 2);
+        assert_eq!(
+            pattern.offsets,
+            vec![Vec2::new(-2., -2.), Vec2::ZERO, Vec2::new(2.5, 2.)]
+        );
+
+        let steps = &patterns.sequences[0].steps;
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].pattern, 1);
+        assert_eq!(steps[0].x_offset, -4.);
+        assert_eq!(steps[1].pattern, 0);
+        assert_eq!(steps[1].delay, 0.8);
+    }
+
+    #[test]
+    fn written_patterns_parse_back_the_same() {
+        let written = write_spawn_patterns(&parse_spawn_patterns(PATTERNS).unwrap());
+        let reparsed = parse_spawn_patterns(&written).unwrap();
+        assert_eq!(write_spawn_patterns(&reparsed), written);
+
+        let original = parse_spawn_patterns(PATTERNS).unwrap();
+        for (a, b) in original.patterns.iter().zip(reparsed.patterns.iter()) {
+            assert_eq!(a.offsets, b.offsets);
+            assert_eq!(
+                (a.min_score, a.max_score, a.weight, a.cooldown),
+                (b.min_score, b.max_score, b.weight, b.cooldown)
+            );
+        }
+        for (a, b) in original.sequences.iter().zip(reparsed.sequences.iter()) {
+            let steps = |sequence: &SpawnSequence| {
+                sequence
+                    .steps
+                    .iter()
+                    .map(|step| (step.pattern, step.delay, step.x_offset))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(steps(a), steps(b));
+        }
+    }
+
+    #[test]
+    fn reports_the_line_of_an_error() {
+        assert_eq!(
+            error("pattern -1 inf 1 0 0,0\n\nwave 1"),
+            "line 3: unknown entry 'wave'"
+        );
+        assert_eq!(
+            error("pattern -1 inf 1 0 0,0\npattern -1 inf 1 0 0,x"),
+            "line 2: invalid offset y 'x'"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert_eq!(error("pattern -1 inf 1"), "line 1: missing cooldown");
+        assert_eq!(
+            error("pattern -1 inf 1 0.5 0,0"),
+            "line 1: invalid cooldown '0.5'"
+        );
+        assert_eq!(
+            error("pattern -1 inf 1 0"),
+            "line 1: pattern has no offsets"
+        );
+        assert_eq!(error("pattern -1 inf 1 0 3"), "line 1: missing offset y");
+        assert_eq!(
+            error("pattern -1 inf 1 0 0,0\nsequence -1 inf 1 0"),
+            "line 2: sequence has no steps"
+        );
+        assert_eq!(
+            error("pattern -1 inf 1 0 0,0\nsequence -1 inf 1 0 0@1"),
+            "line 2: missing step x offset"
+        );
+    }
+
+    #[test]
+    fn rejects_sequences_of_missing_patterns() {
+        assert_eq!(
+            error("pattern -1 inf 1 0 0,0\nsequence -1 inf 1 0 0@0:0 1@1:0"),
+            "sequence step refers to missing pattern 1"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+/// Possible spawn patterns for obstacles, specified as sprite sized offsets from the main
+#[derive(Debug, Clone)]
+pub struct SpawnPattern {
+    pub offsets: Vec<Vec2>,
+    pub min_score: f32,
+
+    /// The pattern stops being picked once the score passes this
+    pub max_score: f32,
+
+    /// The chance of picking this pattern relative to the other available choices
+    pub weight: f32,
+
+    /// How many spawns must pass before this pattern can be picked again
+    pub cooldown: u32,
+}
+
+impl Default for SpawnPattern {
+    fn default() -> Self {
+        SpawnPattern {
+            offsets: vec![Vec2::ZERO],
+            min_score: -1.,
+            max_score: f32::INFINITY,
+            weight: 1.,
+            cooldown: 0,
+        }
+    }
+}
+
+impl SpawnPattern {
+    /// Checks if the pattern can be picked at the given score
+    pub fn is_available(&self, score: f32) -> bool {
+        self.min_score < score && score <= self.max_score
+    }
+}
+
+/// A single step in a spawn sequence
+#[derive(Debug, Clone)]
+pub struct SequenceStep {
+    /// The index of the pattern to spawn in AvailableSpawnPatterns::patterns
+    pub pattern: usize,
+
+    /// Seconds of game time to wait after the previous step before spawning
+    pub delay: f32,
+
+    /// Sprite sized offset from the spawn position of the sequence
+    pub x_offset: f32,
+}
+
+/// A designed wave of patterns that spawn one after the other with set delays
+#[derive(Debug, Clone)]
+pub struct SpawnSequence {
+    pub steps: Vec<SequenceStep>,
+    pub min_score: f32,
+    pub max_score: f32,
+    pub weight: f32,
+    pub cooldown: u32,
+}
+
+impl Default for SpawnSequence {
+    fn default() -> Self {
+        SpawnSequence {
+            steps: vec![],
+            min_score: -1.,
+            max_score: f32::INFINITY,
+            weight: 1.,
+            cooldown: 0,
+        }
+    }
+}
+
+impl SpawnSequence {
+    /// Checks if the sequence can be picked at the given score
+    pub fn is_available(&self, score: f32) -> bool {
+        self.min_score < score && score <= self.max_score
+    }
+}
+
+/// Something that can be picked each time the spawn timer fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpawnChoice {
+    Pattern(usize),
+    Sequence(usize),
+}
+
+/// Counts spawns so recently picked patterns and sequences can cool down
+#[derive(Default)]
+pub struct SpawnHistory {
+    spawn_count: u32,
+    last_spawned: HashMap<SpawnChoice, u32>,
+}
+
+impl SpawnHistory {
+    /// Checks if a choice was picked too recently to be picked again
+    fn is_cooling_down(&self, choice: SpawnChoice, cooldown: u32) -> bool {
+        match self.last_spawned.get(&choice) {
+            Some(last) => self.spawn_count - last < cooldown,
+            None => false,
+        }
+    }
+
+    /// Records that a choice was just spawned
+    pub fn record(&mut self, choice: SpawnChoice) {
+        self.spawn_count += 1;
+        self.last_spawned.insert(choice, self.spawn_count);
+    }
+}
+
+/// The patterns and sequences obstacles are spawned from. The built in set is used until
+/// the pattern file has loaded
+#[derive(Debug, Clone)]
+pub struct AvailableSpawnPatterns {
+    pub patterns: Vec<SpawnPattern>,
+    pub sequences: Vec<SpawnSequence>,
+}
+
+impl AvailableSpawnPatterns {
+    pub fn new() -> Self {
+        AvailableSpawnPatterns {
+            patterns: vec![
+                SpawnPattern {
+                    offsets: vec![Vec2::ZERO],
+                    min_score: -1.,
+                    max_score: 150.,
+                    ..Default::default()
+                },
+                SpawnPattern {
+                    offsets: vec![Vec2::new(-1., 0.), Vec2::ZERO, Vec2::new(1., 0.)],
+                    min_score: -1.,
+                    cooldown: 1,
+                    ..Default::default()
+                },
+                SpawnPattern {
+                    offsets: vec![
+                        Vec2::new(-2., 0.),
+                        Vec2::new(-1., 0.),
+                        Vec2::ZERO,
+                        Vec2::new(1., 0.),
+                        Vec2::new(2., 0.),
+                    ],
+                    min_score: 50.,
+                    weight: 0.8,
+                    cooldown: 1,
+                    ..Default::default()
+                },
+                SpawnPattern {
+                    offsets: vec![
+                        Vec2::new(-2., 0.),
+                        Vec2::ZERO,
+                        Vec2::new(1., 0.),
+                        Vec2::new(2., 0.),
+                    ],
+                    min_score: 60.,
+                    weight: 0.8,
+                    cooldown: 2,
+                    ..Default::default()
+                },
+                SpawnPattern {
+                    offsets: vec![Vec2::new(-2., -2.), Vec2::ZERO, Vec2::new(2., 2.)],
+                    min_score: 30.,
+                    weight: 0.6,
+                    cooldown: 2,
+                    ..Default::default()
+                },
+                SpawnPattern {
+                    offsets: vec![
+                        Vec2::new(-4., 0.),
+                        Vec2::new(-2., 0.),
+                        Vec2::ZERO,
+                        Vec2::new(2., 0.),
+                        Vec2::new(4., 0.),
+                    ],
+                    min_score: 100.,
+                    weight: 0.5,
+                    cooldown: 2,
+                    ..Default::default()
+                },
+            ],
+            sequences: vec![
+                // single hay stepping across the field
+                SpawnSequence {
+                    steps: vec![
+                        SequenceStep {
+                            pattern: 0,
+                            delay: 0.,
+                            x_offset: -3.,
+                        },
+                        SequenceStep {
+                            pattern: 0,
+                            delay: 0.5,
+                            x_offset: 0.,
+                        },
+                        SequenceStep {
+                            pattern: 0,
+                            delay: 0.5,
+                            x_offset: 3.,
+                        },
+                    ],
+                    min_score: 20.,
+                    weight: 0.5,
+                    cooldown: 3,
+                    ..Default::default()
+                },
+                // two short lines either side of the spawn point
+                SpawnSequence {
+                    steps: vec![
+                        SequenceStep {
+                            pattern: 1,
+                            delay: 0.,
+                            x_offset: -4.,
+                        },
+                        SequenceStep {
+                            pattern: 1,
+                            delay: 0.8,
+                            x_offset: 4.,
+                        },
+                    ],
+                    min_score: 40.,
+                    weight: 0.5,
+                    cooldown: 3,
+                    ..Default::default()
+                },
+                // a wave of three lines
+                SpawnSequence {
+                    steps: vec![
+                        SequenceStep {
+                            pattern: 1,
+                            delay: 0.,
+                            x_offset: 0.,
+                        },
+                        SequenceStep {
+                            pattern: 2,
+                            delay: 1.,
+                            x_offset: -3.,
+                        },
+                        SequenceStep {
+                            pattern: 1,
+                            delay: 1.,
+                            x_offset: 3.,
+                        },
+                    ],
+                    min_score: 80.,
+                    weight: 0.4,
+                    cooldown: 4,
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    /// Picks a pattern or sequence to spawn at the given score. Choices that are cooling
+    /// down are skipped unless nothing else is available. Pattern weights are scaled by
+    /// `pattern_weight`, e.g. so the difficulty director can favour larger patterns.
+    pub fn choose<R: Rng, F: Fn(&SpawnPattern) -> f32>(
+        &self,
+        score: f32,
+        history: &SpawnHistory,
+        pattern_weight: F,
+        rng: &mut R,
+    ) -> Option<SpawnChoice> {
+        let candidates = self
+            .patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, pattern)| pattern.is_available(score))
+            .map(|(idx, pattern)| {
+                (
+                    SpawnChoice::Pattern(idx),
+                    pattern.weight * pattern_weight(pattern),
+                    pattern.cooldown,
+                )
+            })
+            .chain(
+                self.sequences
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, sequence)| sequence.is_available(score))
+                    .map(|(idx, sequence)| {
+                        (
+                            SpawnChoice::Sequence(idx),
+                            sequence.weight,
+                            sequence.cooldown,
+                        )
+                    }),
+            )
+            .filter(|(_, weight, _)| *weight > 0.)
+            .collect::<Vec<_>>();
+
+        let ready = candidates
+            .iter()
+            .filter(|(choice, _, cooldown)| !history.is_cooling_down(*choice, *cooldown))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let candidates = if ready.is_empty() { candidates } else { ready };
+
+        candidates
+            .choose_weighted(rng, |(_, weight, _)| *weight)
+            .ok()
+            .map(|(choice, _, _)| *choice)
+    }
+}
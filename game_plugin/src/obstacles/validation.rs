@@ -0,0 +1,654 @@
+use crate::{
+    difficulty::Difficulty,
+    game_map::GameMap,
+    obstacles::{
+        patterns::{AvailableSpawnPatterns, SpawnChoice, SpawnPattern},
+        OBSTACLE_FALL_SPEED,
+    },
+    player::{MAX_TRACTORS, PLAYER_LIFT_SCALE, PLAYER_MOVE_SCALE, PLAYER_SPEED, TETHER_LENGTH},
+};
+
+/// The size of the grid (in world units) that tractor positions are checked on
+const GRID_STEP: f32 = 8.;
+
+/// Times closer than this are treated as the same crossing
+const TIME_EPSILON: f32 = 1e-4;
+
+/// What is wrong with a spawn pattern at a particular spawn position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternIssueKind {
+    /// Some of the obstacles spawn outside of `GameMap::get_x_bound()`
+    OutsideBounds,
+
+    /// Some starting tractor positions can't avoid hitting an obstacle
+    Unavoidable,
+
+    /// No tractor positions can capture any of the obstacles
+    Uncapturable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternIssue {
+    pub choice: SpawnChoice,
+    pub spawn_x: f32,
+    pub kind: PatternIssueKind,
+
+    /// How many tractors the pattern was checked with
+    pub tractors: usize,
+}
+
+/// The game rules the patterns are checked against
+#[derive(Debug, Clone, Copy)]
+pub struct SolvabilityParams {
+    /// How fast obstacles fall, in world units per second
+    pub fall_speed: f32,
+
+    /// How fast each tractor moves across the map, and up and down its lane, in world
+    /// units per second
+    pub tractor_speed: f32,
+    pub lift_speed: f32,
+
+    /// How many tractors are tethered together
+    pub tractors: usize,
+
+    /// How far apart neighbouring tractors can be without straining their tether
+    pub max_separation: f32,
+
+    /// How close an obstacle can pass to a tractor without hitting it
+    pub obstacle_collision_distance: f32,
+
+    /// How close the tractors can get without crashing into each other
+    pub tractor_collision_distance: f32,
+}
+
+impl SolvabilityParams {
+    /// Gets the parameters for a difficulty and number of tractors at a given game speed
+    /// multiplier. Tractors move at the same speed regardless of the multiplier, while
+    /// obstacles speed up. Shop upgrades aren't counted, so patterns are checked against
+    /// the slowest tractors and shortest tethers.
+    pub fn new(
+        game_map: &GameMap,
+        difficulty: &Difficulty,
+        multiplier: f32,
+        tractors: usize,
+    ) -> Self {
+        SolvabilityParams {
+            fall_speed: OBSTACLE_FALL_SPEED * multiplier,
+            tractor_speed: PLAYER_SPEED * PLAYER_MOVE_SCALE,
+            lift_speed: PLAYER_SPEED * PLAYER_MOVE_SCALE * PLAYER_LIFT_SCALE,
+            tractors: tractors.max(2).min(MAX_TRACTORS),
+            max_separation: TETHER_LENGTH * game_map.sprite_size,
+            obstacle_collision_distance: difficulty.obstacle_collision_distance
+                * game_map.sprite_size,
+            tractor_collision_distance: difficulty.tractor_collision_distance
+                * game_map.sprite_size,
+        }
+    }
+}
+
+/// Every x position a pattern can be spawned at, matching `spawn_obstacles`
+pub fn possible_spawn_xs(game_map: &GameMap) -> Vec<f32> {
+    let half_width = (game_map.width / 2.).floor() as i32;
+    (-half_width..=half_width)
+        .map(|x| x as f32 * game_map.sprite_size)
+        .collect()
+}
+
+/// Checks every pattern and sequence can be handled from any tractor position, and
+/// that they stay inside the map. Sequences are only checked against the map bounds.
+pub fn validate_spawn_patterns(
+    patterns: &AvailableSpawnPatterns,
+    game_map: &GameMap,
+    params: &SolvabilityParams,
+) -> Vec<PatternIssue> {
+    let mut issues = vec![];
+    let space = TractorSpace::new(game_map, params);
+
+    for spawn_x in possible_spawn_xs(game_map) {
+        for (idx, pattern) in patterns.patterns.iter().enumerate() {
+            let choice = SpawnChoice::Pattern(idx);
+
+            if pattern_outside_bounds(pattern, spawn_x, game_map) {
+                issues.push(PatternIssue {
+                    choice,
+                    spawn_x,
+                    kind: PatternIssueKind::OutsideBounds,
+                    tractors: params.tractors,
+                });
+            }
+
+            let (avoidable, capturable) = check_pattern(pattern, spawn_x, game_map, params, &space);
+            if !avoidable {
+                issues.push(PatternIssue {
+                    choice,
+                    spawn_x,
+                    kind: PatternIssueKind::Unavoidable,
+                    tractors: params.tractors,
+                });
+            }
+            if !capturable {
+                issues.push(PatternIssue {
+                    choice,
+                    spawn_x,
+                    kind: PatternIssueKind::Uncapturable,
+                    tractors: params.tractors,
+                });
+            }
+        }
+
+        for (idx, sequence) in patterns.sequences.iter().enumerate() {
+            let outside = sequence.steps.iter().any(|step| {
+                patterns
+                    .patterns
+                    .get(step.pattern)
+                    .map(|pattern| {
+                        pattern_outside_bounds(
+                            pattern,
+                            spawn_x + step.x_offset * game_map.sprite_size,
+                            game_map,
+                        )
+                    })
+                    .unwrap_or(true)
+            });
+
+            if outside {
+                issues.push(PatternIssue {
+                    choice: SpawnChoice::Sequence(idx),
+                    spawn_x,
+                    kind: PatternIssueKind::OutsideBounds,
+                    tractors: params.tractors,
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Checks if any obstacle in the pattern spawns outside of the map
+fn pattern_outside_bounds(pattern: &SpawnPattern, spawn_x: f32, game_map: &GameMap) -> bool {
+    let x_bound = game_map.get_x_bound();
+    pattern.offsets.iter().any(|offset| {
+        let x = spawn_x + offset.x * game_map.sprite_size;
+        x < -x_bound || x > x_bound
+    })
+}
+
+/// The grid of tractor positions a TractorSpace is built from. Only positions where every
+/// pair of neighbouring tractors is far enough apart not to crash and close enough not to
+/// strain their tether are stored: each cell is the leftmost tractor's position and the gap
+/// to each tractor after it.
+#[derive(Debug, Clone, Copy)]
+struct GridShape {
+    tractors: usize,
+
+    /// How many positions across the map each tractor can be at
+    size: usize,
+
+    /// The smallest gap between neighbouring tractors, and how many gaps there are, in cells
+    min_gap: usize,
+    gaps: usize,
+}
+
+/// The leftmost tractor's cell, then the gap index of each tractor after it
+type GridCoords = [usize; MAX_TRACTORS];
+
+impl GridShape {
+    fn len(&self) -> usize {
+        self.size * self.gaps.pow(self.tractors as u32 - 1)
+    }
+
+    fn coords(&self, idx: usize) -> GridCoords {
+        let mut coords = [0; MAX_TRACTORS];
+        coords[0] = idx % self.size;
+        let mut rest = idx / self.size;
+        for coord in coords.iter_mut().take(self.tractors).skip(1) {
+            *coord = rest % self.gaps;
+            rest /= self.gaps;
+        }
+        coords
+    }
+
+    /// The cell index for some coordinates, if every tractor is on the map
+    fn index(&self, coords: &[isize; MAX_TRACTORS]) -> Option<usize> {
+        let mut rightmost = coords[0];
+        if rightmost < 0 || rightmost >= self.size as isize {
+            return None;
+        }
+
+        let mut idx = 0;
+        for &gap in coords[1..self.tractors].iter().rev() {
+            if gap < 0 || gap >= self.gaps as isize {
+                return None;
+            }
+            rightmost += self.min_gap as isize + gap;
+            idx = idx * self.gaps + gap as usize;
+        }
+
+        if rightmost >= self.size as isize {
+            return None;
+        }
+        Some(idx * self.size + coords[0] as usize)
+    }
+
+    /// The cell each tractor is in, from the leftmost
+    fn tractor_cells(&self, coords: &GridCoords) -> GridCoords {
+        let mut cells = *coords;
+        for tractor in 1..self.tractors {
+            cells[tractor] = cells[tractor - 1] + self.min_gap + coords[tractor];
+        }
+        cells
+    }
+
+    fn on_map(&self, idx: usize) -> bool {
+        let coords = self.coords(idx);
+        self.tractor_cells(&coords)[self.tractors - 1] < self.size
+    }
+
+    /// Every way of moving each tractor by at most one cell, as changes to the coordinates
+    fn moves(&self) -> Vec<[isize; MAX_TRACTORS]> {
+        let mut moves = vec![];
+        for combination in 0..3usize.pow(self.tractors as u32) {
+            // each tractor moves -1, 0 or 1 cells
+            let mut steps = [0; MAX_TRACTORS];
+            let mut rest = combination;
+            for step in steps.iter_mut().take(self.tractors) {
+                *step = (rest % 3) as isize - 1;
+                rest /= 3;
+            }
+            if steps.iter().all(|step| *step == 0) {
+                continue;
+            }
+
+            // moving a tractor changes its gap to the one before it and the one after it
+            let mut change = [0; MAX_TRACTORS];
+            change[0] = steps[0];
+            for tractor in 1..self.tractors {
+                change[tractor] = steps[tractor] - steps[tractor - 1];
+            }
+            moves.push(change);
+        }
+        moves
+    }
+}
+
+/// Every legal tractor position and the moves between them. This only depends on the map
+/// and the solvability params, so it is worked out once and shared by every check.
+struct TractorSpace {
+    tractors: usize,
+
+    /// Where each tractor is across the map at each legal position, from the leftmost
+    positions: Vec<[f32; MAX_TRACTORS]>,
+
+    /// The positions one step away from each position are
+    /// `neighbours[starts[idx]..starts[idx + 1]]`
+    starts: Vec<usize>,
+    neighbours: Vec<usize>,
+}
+
+impl TractorSpace {
+    fn new(game_map: &GameMap, params: &SolvabilityParams) -> Self {
+        let x_bound = game_map.get_x_bound();
+        let min_gap = (params.tractor_collision_distance / GRID_STEP).ceil() as usize;
+        let max_gap = (params.max_separation / GRID_STEP).floor() as usize;
+        let shape = GridShape {
+            tractors: params.tractors,
+            size: (2. * x_bound / GRID_STEP).floor() as usize + 1,
+            min_gap,
+            gaps: (max_gap + 1).saturating_sub(min_gap),
+        };
+
+        // number the legal cells of the grid
+        let mut legal = vec![None; shape.len()];
+        let mut cells = vec![];
+        for (idx, legal) in legal.iter_mut().enumerate() {
+            if shape.on_map(idx) {
+                *legal = Some(cells.len());
+                cells.push(idx);
+            }
+        }
+
+        let moves = shape.moves();
+        let mut positions = Vec::with_capacity(cells.len());
+        let mut starts = Vec::with_capacity(cells.len() + 1);
+        let mut neighbours = vec![];
+        for idx in cells {
+            let coords = shape.coords(idx);
+
+            let mut xs = [0.; MAX_TRACTORS];
+            for (x, cell) in xs.iter_mut().zip(shape.tractor_cells(&coords).iter()) {
+                *x = -x_bound + *cell as f32 * GRID_STEP;
+            }
+            positions.push(xs);
+
+            starts.push(neighbours.len());
+            for change in moves.iter() {
+                let mut moved = [0; MAX_TRACTORS];
+                for ((moved, coord), change) in moved.iter_mut().zip(coords.iter()).zip(change) {
+                    *moved = *coord as isize + change;
+                }
+                if let Some(neighbour) = shape.index(&moved).and_then(|idx| legal[idx]) {
+                    neighbours.push(neighbour);
+                }
+            }
+        }
+        starts.push(neighbours.len());
+
+        TractorSpace {
+            tractors: params.tractors,
+            positions,
+            starts,
+            neighbours,
+        }
+    }
+
+    fn neighbours(&self, idx: usize) -> &[usize] {
+        &self.neighbours[self.starts[idx]..self.starts[idx + 1]]
+    }
+}
+
+/// A set of legal tractor positions in a TractorSpace
+#[derive(Clone)]
+struct TractorGrid {
+    cells: Vec<bool>,
+}
+
+impl TractorGrid {
+    /// Every legal tractor position
+    fn all(space: &TractorSpace) -> Self {
+        TractorGrid {
+            cells: vec![true; space.positions.len()],
+        }
+    }
+
+    /// The legal tractor positions passing a check on where each tractor is in the world
+    fn from_positions(space: &TractorSpace, check: impl Fn(&[f32]) -> bool) -> Self {
+        TractorGrid {
+            cells: space
+                .positions
+                .iter()
+                .map(|xs| check(&xs[..space.tractors]))
+                .collect(),
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.cells.iter().any(|cell| *cell)
+    }
+
+    /// Keeps only the cells that are also set in `other`
+    fn and(&mut self, other: &TractorGrid) {
+        for (cell, other) in self.cells.iter_mut().zip(other.cells.iter()) {
+            *cell = *cell && *other;
+        }
+    }
+
+    /// Checks every cell set in `other` is also set here
+    fn contains(&self, other: &TractorGrid) -> bool {
+        self.cells
+            .iter()
+            .zip(other.cells.iter())
+            .all(|(cell, other)| *cell || !*other)
+    }
+
+    /// Every legal position the tractors could get to from the set cells in the time it
+    /// takes to move `steps` cells. The tractors move at the same time, each by up to a
+    /// cell per step, and only through legal positions.
+    fn dilate(&self, space: &TractorSpace, steps: usize) -> TractorGrid {
+        let mut result = self.clone();
+        let mut frontier = (0..self.cells.len())
+            .filter(|idx| self.cells[*idx])
+            .collect::<Vec<_>>();
+
+        for _ in 0..steps {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut next = vec![];
+            for idx in frontier {
+                for &neighbour in space.neighbours(idx) {
+                    if !result.cells[neighbour] {
+                        result.cells[neighbour] = true;
+                        next.push(neighbour);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        result
+    }
+}
+
+/// Checks a pattern at a spawn position, returning whether it can be avoided from every
+/// starting tractor position, and whether any obstacle in it can be captured.
+///
+/// The tractors are free to move anywhere they can legally be: on the map, not touching,
+/// and close enough that their tethers don't strain. Obstacles only matter at the moment
+/// they cross the tether line, which is checked with every tractor at the bottom of its
+/// lane. Tractors drive up and down their lane at the same time as across it, so any
+/// tractor up the lane can get back down on the way to where it needs to be.
+fn check_pattern(
+    pattern: &SpawnPattern,
+    spawn_x: f32,
+    game_map: &GameMap,
+    params: &SolvabilityParams,
+    space: &TractorSpace,
+) -> (bool, bool) {
+    let region = TractorGrid::all(space);
+
+    // work out when each obstacle crosses the tether line, grouping those that cross together
+    let spawn_height =
+        game_map.top_y() + game_map.pad_y * 3. * game_map.sprite_size - game_map.bottom_y();
+    let mut crossings = pattern
+        .offsets
+        .iter()
+        .map(|offset| {
+            (
+                (spawn_height + offset.y * game_map.sprite_size) / params.fall_speed,
+                spawn_x + offset.x * game_map.sprite_size,
+            )
+        })
+        .collect::<Vec<_>>();
+    crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut events: Vec<(f32, Vec<f32>)> = vec![];
+    for (time, x) in crossings {
+        match events.last_mut() {
+            Some(last) if (time - last.0).abs() < TIME_EPSILON => last.1.push(x),
+            _ => events.push((time, vec![x])),
+        }
+    }
+
+    if events.is_empty() {
+        return (true, true);
+    }
+
+    // if a tractor starting at the top of its lane can't get down before the first obstacle
+    // reaches the top of the lane, that obstacle could cross while it is still up there
+    let lane = game_map.lane_height * game_map.sprite_size;
+    let first_crossing = if lane / params.lift_speed > events[0].0 - lane / params.fall_speed {
+        events[0].0 - lane / params.fall_speed
+    } else {
+        events[0].0
+    };
+
+    // the positions that are safe and the positions that capture hay at each crossing
+    let hits = |xs: &[f32], tractors: &[f32]| {
+        xs.iter().any(|x| {
+            tractors
+                .iter()
+                .any(|tractor| (tractor - x).abs() < params.obstacle_collision_distance)
+        })
+    };
+    let mut not_hit = vec![];
+    let mut captures = vec![];
+    for (_, xs) in events.iter() {
+        not_hit.push(TractorGrid::from_positions(space, |tractors| {
+            !hits(xs, tractors)
+        }));
+        captures.push(TractorGrid::from_positions(space, |tractors| {
+            let (left, right) = (tractors[0], tractors[tractors.len() - 1]);
+            !hits(xs, tractors) && xs.iter().any(|x| *x > left && *x < right)
+        }));
+    }
+
+    let steps_for = |dt: f32| (params.tractor_speed * dt.max(0.) / GRID_STEP).floor() as usize;
+
+    // work backwards to find the positions at each crossing that survive the rest of the pattern
+    let mut survivable: Vec<TractorGrid> = Vec::with_capacity(events.len());
+    for idx in (0..events.len()).rev() {
+        let mut safe = match survivable.last() {
+            Some(next) => next.dilate(space, steps_for(events[idx + 1].0 - events[idx].0)),
+            None => region.clone(),
+        };
+        safe.and(&not_hit[idx]);
+        survivable.push(safe);
+    }
+    survivable.reverse();
+
+    let start = survivable[0].dilate(space, steps_for(first_crossing));
+    let avoidable = start.contains(&region);
+
+    // work forwards to see if any capture is reachable and survivable. The tractors could
+    // have started anywhere, so they could be anywhere at the first crossing
+    let mut capturable = false;
+    let mut reachable = region.clone();
+    for idx in 0..events.len() {
+        let mut capture = captures[idx].clone();
+        capture.and(&reachable);
+        capture.and(&survivable[idx]);
+        capturable = capturable || capture.any();
+
+        reachable.and(&not_hit[idx]);
+        if idx + 1 < events.len() {
+            reachable = reachable.dilate(space, steps_for(events[idx + 1].0 - events[idx].0));
+        }
+    }
+
+    (avoidable, capturable)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::difficulty::DifficultyPreset;
+
+    fn params(game_map: &GameMap, tractors: usize) -> SolvabilityParams {
+        SolvabilityParams::new(game_map, &DifficultyPreset::Normal.settings(), 2., tractors)
+    }
+
+    /// A single pattern with obstacles in a row at the given sprite offsets
+    fn row(xs: impl Iterator<Item = i32>) -> AvailableSpawnPatterns {
+        AvailableSpawnPatterns {
+            patterns: vec![SpawnPattern {
+                offsets: xs.map(|x| Vec2::new(x as f32, 0.)).collect(),
+                ..Default::default()
+            }],
+            sequences: vec![],
+        }
+    }
+
+    /// The kinds of issue the pattern has when spawned in the middle of the map
+    fn issues_in_middle(
+        patterns: &AvailableSpawnPatterns,
+        game_map: &GameMap,
+        params: &SolvabilityParams,
+    ) -> Vec<PatternIssueKind> {
+        validate_spawn_patterns(patterns, game_map, params)
+            .iter()
+            .filter(|issue| issue.spawn_x == 0.)
+            .map(|issue| issue.kind)
+            .collect()
+    }
+
+    #[test]
+    fn built_in_patterns_can_be_avoided() {
+        let game_map = GameMap::default();
+
+        for tractors in 2..=MAX_TRACTORS {
+            let issues = validate_spawn_patterns(
+                &AvailableSpawnPatterns::new(),
+                &game_map,
+                &params(&game_map, tractors),
+            );
+
+            assert!(issues
+                .iter()
+                .all(|issue| issue.kind != PatternIssueKind::Unavoidable));
+        }
+    }
+
+    #[test]
+    fn flags_patterns_that_need_the_tethers_stretched() {
+        let game_map = GameMap::default();
+        // capturing any of this hay needs the tractors 8 sprites apart, around the block
+        let patterns = row(-3..=3);
+
+        let issues = issues_in_middle(&patterns, &game_map, &params(&game_map, 2));
+        assert!(issues.contains(&PatternIssueKind::Uncapturable));
+        assert!(!issues.contains(&PatternIssueKind::Unavoidable));
+
+        let long_tethers = SolvabilityParams {
+            max_separation: 8. * game_map.sprite_size,
+            ..params(&game_map, 2)
+        };
+        let issues = issues_in_middle(&patterns, &game_map, &long_tethers);
+        assert!(!issues.contains(&PatternIssueKind::Uncapturable));
+    }
+
+    #[test]
+    fn flags_patterns_that_hit_the_middle_tractor() {
+        let game_map = GameMap::default();
+        // a wall of hay across the map, with room for only two tractors
+        let patterns = row((-10..=10).filter(|x: &i32| x.abs() != 2));
+
+        let issues = issues_in_middle(&patterns, &game_map, &params(&game_map, 2));
+        assert!(issues.is_empty());
+
+        let issues = issues_in_middle(&patterns, &game_map, &params(&game_map, 3));
+        assert!(issues.contains(&PatternIssueKind::Unavoidable));
+    }
+
+    #[test]
+    fn flags_patterns_that_cover_the_map() {
+        let game_map = GameMap::default();
+        let patterns = AvailableSpawnPatterns {
+            patterns: vec![SpawnPattern {
+                offsets: (-24..=24).map(|x| Vec2::new(x as f32, 0.)).collect(),
+                ..Default::default()
+            }],
+            sequences: vec![],
+        };
+
+        let issues = validate_spawn_patterns(&patterns, &game_map, &params(&game_map, 2));
+
+        assert!(issues
+            .iter()
+            .any(|issue| issue.kind == PatternIssueKind::Unavoidable));
+        assert!(issues
+            .iter()
+            .any(|issue| issue.kind == PatternIssueKind::Uncapturable));
+    }
+
+    #[test]
+    fn flags_patterns_outside_the_map() {
+        let game_map = GameMap::default();
+        let patterns = AvailableSpawnPatterns {
+            patterns: vec![SpawnPattern {
+                offsets: vec![Vec2::new(game_map.width, 0.)],
+                ..Default::default()
+            }],
+            sequences: vec![],
+        };
+
+        let issues = validate_spawn_patterns(&patterns, &game_map, &params(&game_map, 2));
+
+        assert!(issues
+            .iter()
+            .any(|issue| issue.kind == PatternIssueKind::OutsideBounds));
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    math::Vec2,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::obstacles::pattern_file::{parse_offset, parse_value};
+
+/// The wave script used until the script file has loaded, which spawns a pattern or
+/// sequence every spawn interval
+pub const DEFAULT_WAVE_SCRIPT: &str = "
+label loop
+wait interval
+position random
+random
+goto loop
+";
+
+/// How many wave script instructions can run in one frame, so a loop without a wait
+/// can't freeze the game
+pub const MAX_WAVE_INSTRUCTIONS_PER_FRAME: usize = 100;
+
+/// Where a wave spawns its obstacles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WavePosition {
+    /// A random sprite aligned position on the map, as the spawn timer used to pick
+    Random,
+
+    /// A fixed position, in sprites from the center of the map
+    Fixed(f32),
+}
+
+/// How long a wave waits before carrying on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaveWait {
+    /// Seconds of game time
+    Seconds(f32),
+
+    /// The difficulty's spawn interval, scaled by the difficulty director
+    Interval,
+}
+
+/// Something a wave script can branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveValue {
+    Score,
+
+    /// Elapsed game time in seconds
+    Time,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveComparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl WaveComparison {
+    pub fn compare(&self, value: f32, threshold: f32) -> bool {
+        match self {
+            WaveComparison::Less => value < threshold,
+            WaveComparison::LessOrEqual => value <= threshold,
+            WaveComparison::Greater => value > threshold,
+            WaveComparison::GreaterOrEqual => value >= threshold,
+        }
+    }
+}
+
+/// A single line of a wave script. Jumps are resolved to instruction indices when parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaveInstruction {
+    Position(WavePosition),
+
+    /// Spawns obstacles at sprite sized offsets from the wave position
+    Spawn(Vec<Vec2>),
+
+    /// Spawns a pattern (an index into AvailableSpawnPatterns::patterns) at the wave position
+    Pattern(usize),
+
+    /// Spawns a pattern or sequence picked by weight, as the spawn timer used to
+    Random,
+
+    Wait(WaveWait),
+    Goto(usize),
+    If {
+        value: WaveValue,
+        comparison: WaveComparison,
+        threshold: f32,
+        target: usize,
+    },
+
+    /// Sets the music intensity, from 0 (calm) to 1 (intense)
+    Music(f32),
+    End,
+}
+
+/// A wave script loaded from a `.wave` file. Each line is one instruction, and `#`
+/// starts a comment:
+///
+/// ```text
+/// position random | position <x>          where the next spawns are centered, in sprites
+/// spawn <x>,<y> ...                       spawns clouds at sprite offsets from the position
+/// pattern <index>                         spawns a pattern from the pattern file
+/// random                                  spawns a pattern or sequence picked by weight
+/// wait <seconds> | wait interval          waits for game time or the spawn interval
+/// if <score|time> <op> <value> goto <label>   branches, where op is <, <=, > or >=
+/// label <name>, goto <label>, music <intensity>, end
+/// ```
+///
+/// A script that reaches the end stops spawning, so most scripts finish with a `goto`.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "c41f9a52-7e0d-4b36-8f1a-5d2e9b7c3a60"]
+pub struct WaveScript {
+    pub instructions: Vec<WaveInstruction>,
+}
+
+impl Default for WaveScript {
+    fn default() -> Self {
+        parse_wave_script(DEFAULT_WAVE_SCRIPT).expect("the default wave script is valid")
+    }
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let contents = std::str::from_utf8(bytes)?;
+            let script = parse_wave_script(contents).map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(script));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wave"]
+    }
+}
+
+/// Where a wave script is up to
+#[derive(Debug, Default)]
+pub struct WaveRunner {
+    pub next_instruction: usize,
+
+    /// Seconds of game time left to wait before running the next instruction
+    pub wait: f32,
+
+    /// The world x position obstacles are spawned around
+    pub spawn_x: f32,
+}
+
+impl WaveRunner {
+    /// Runs the script until it waits or ends, after `delta` seconds of game time. Jumps and
+    /// waits are handled here, and every other instruction is passed to `handle` along with
+    /// the runner, which returns whether to keep running this frame.
+    pub fn run(
+        &mut self,
+        script: &WaveScript,
+        delta: f32,
+        spawn_interval: f32,
+        value: impl Fn(WaveValue) -> f32,
+        mut handle: impl FnMut(&mut WaveRunner, &WaveInstruction) -> bool,
+    ) {
+        self.wait -= delta;
+
+        let mut instructions_run = 0;
+        while self.wait <= 0. {
+            let instruction = match script.instructions.get(self.next_instruction) {
+                Some(instruction) => instruction,
+                None => return, // the script has finished
+            };
+
+            instructions_run += 1;
+            if instructions_run > MAX_WAVE_INSTRUCTIONS_PER_FRAME {
+                println!("Wave script is looping without waiting, carrying on next frame");
+                return;
+            }
+
+            self.next_instruction += 1;
+
+            match instruction {
+                WaveInstruction::Wait(WaveWait::Seconds(seconds)) => {
+                    self.wait += seconds;
+                }
+                WaveInstruction::Wait(WaveWait::Interval) => {
+                    self.wait += spawn_interval;
+                }
+                WaveInstruction::Goto(target) => {
+                    self.next_instruction = *target;
+                }
+                WaveInstruction::If {
+                    value: branch_value,
+                    comparison,
+                    threshold,
+                    target,
+                } => {
+                    if comparison.compare(value(*branch_value), *threshold) {
+                        self.next_instruction = *target;
+                    }
+                }
+                WaveInstruction::End => {
+                    self.next_instruction = script.instructions.len();
+                }
+                _ => {
+                    if !handle(self, instruction) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses the contents of a `.wave` file
+pub fn parse_wave_script(contents: &str) -> Result<WaveScript, String> {
+    // find the labels first so jumps can go forwards
+    let mut labels = HashMap::new();
+    let mut lines = vec![];
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some("label") {
+            let name = parts
+                .next()
+                .ok_or_else(|| format!("line {}: missing label name", line_idx + 1))?;
+            if labels.insert(name, lines.len()).is_some() {
+                return Err(format!("line {}: duplicate label '{}'", line_idx + 1, name));
+            }
+        } else {
+            lines.push((line_idx, line));
+        }
+    }
+
+    let instructions = lines
+        .iter()
+        .map(|(line_idx, line)| {
+            parse_instruction(line, &labels).map_err(|e| format!("line {}: {}", line_idx + 1, e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(WaveScript { instructions })
+}
+
+fn parse_instruction(line: &str, labels: &HashMap<&str, usize>) -> Result<WaveInstruction, String> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().unwrap_or("");
+
+    let instruction = match command {
+        "position" => match parts.next() {
+            Some("random") => WaveInstruction::Position(WavePosition::Random),
+            x => WaveInstruction::Position(WavePosition::Fixed(parse_value(x, "position")?)),
+        },
+        "spawn" => {
+            let offsets = parts
+                .by_ref()
+                .map(parse_offset)
+                .collect::<Result<Vec<_>, String>>()?;
+
+            if offsets.is_empty() {
+                return Err("spawn needs at least one offset".into());
+            }
+
+            WaveInstruction::Spawn(offsets)
+        }
+        "pattern" => WaveInstruction::Pattern(parse_value(parts.next(), "pattern")?),
+        "random" => WaveInstruction::Random,
+        "wait" => match parts.next() {
+            Some("interval") => WaveInstruction::Wait(WaveWait::Interval),
+            seconds => WaveInstruction::Wait(WaveWait::Seconds(parse_value(seconds, "wait")?)),
+        },
+        "goto" => WaveInstruction::Goto(parse_label(parts.next(), labels)?),
+        "if" => {
+            let value = match parts.next() {
+                Some("score") => WaveValue::Score,
+                Some("time") => WaveValue::Time,
+                other => return Err(format!("can't branch on '{}'", other.unwrap_or(""))),
+            };
+            let comparison = match parts.next() {
+                Some("<") => WaveComparison::Less,
+                Some("<=") => WaveComparison::LessOrEqual,
+                Some(">") => WaveComparison::Greater,
+                Some(">=") => WaveComparison::GreaterOrEqual,
+                other => return Err(format!("unknown comparison '{}'", other.unwrap_or(""))),
+            };
+            let threshold = parse_value(parts.next(), "threshold")?;
+            if parts.next() != Some("goto") {
+                return Err("expected 'goto' after the condition".into());
+            }
+
+            WaveInstruction::If {
+                value,
+                comparison,
+                threshold,
+                target: parse_label(parts.next(), labels)?,
+            }
+        }
+        "music" => {
+            let intensity: f32 = parse_value(parts.next(), "music intensity")?;
+            WaveInstruction::Music(intensity.max(0.).min(1.))
+        }
+        "end" => WaveInstruction::End,
+        _ => return Err(format!("unknown instruction '{}'", command)),
+    };
+
+    match parts.next() {
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+        None => Ok(instruction),
+    }
+}
+
+fn parse_label(label: Option<&str>, labels: &HashMap<&str, usize>) -> Result<usize, String> {
+    let label = label.ok_or("missing label")?;
+    labels
+        .get(label)
+        .copied()
+        .ok_or_else(|| format!("unknown label '{}'", label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Vec<WaveInstruction> {
+        parse_wave_script(contents).unwrap().instructions
+    }
+
+    fn error(contents: &str) -> String {
+        parse_wave_script(contents).unwrap_err()
+    }
+
+    #[test]
+    fn parses_each_instruction() {
+        assert_eq!(
+            parse(
+                "
+                # comment
+                position random
+                position -2.5  # trailing comment
+                spawn 0,0 1,-1
+                pattern 3
+                random
+                wait 0.5
+                wait interval
+                music 0.8
+                end
+                "
+            ),
+            vec![
+                WaveInstruction::Position(WavePosition::Random),
+                WaveInstruction::Position(WavePosition::Fixed(-2.5)),
+                WaveInstruction::Spawn(vec![Vec2::new(0., 0.), Vec2::new(1., -1.)]),
+                WaveInstruction::Pattern(3),
+                WaveInstruction::Random,
+                WaveInstruction::Wait(WaveWait::Seconds(0.5)),
+                WaveInstruction::Wait(WaveWait::Interval),
+                WaveInstruction::Music(0.8),
+                WaveInstruction::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn shipped_scripts_parse() {
+        WaveScript::default();
+        parse_wave_script(include_str!("../../../assets/waves/default.wave")).unwrap();
+    }
+
+    #[test]
+    fn clamps_music_intensity() {
+        assert_eq!(
+            parse("music 2\nmusic -1"),
+            vec![WaveInstruction::Music(1.), WaveInstruction::Music(0.)]
+        );
+    }
+
+    #[test]
+    fn resolves_labels_to_instructions() {
+        let instructions = parse(
+            "
+            goto skip
+            label top
+            random
+            label skip
+            if score >= 100 goto top
+            if time < 30 goto skip
+            if score <= 5 goto top
+            if time > 60 goto top
+            goto top
+            ",
+        );
+
+        let branch = |value, comparison, threshold, target| WaveInstruction::If {
+            value,
+            comparison,
+            threshold,
+            target,
+        };
+        assert_eq!(
+            instructions,
+            vec![
+                WaveInstruction::Goto(2),
+                WaveInstruction::Random,
+                branch(WaveValue::Score, WaveComparison::GreaterOrEqual, 100., 1),
+                branch(WaveValue::Time, WaveComparison::Less, 30., 2),
+                branch(WaveValue::Score, WaveComparison::LessOrEqual, 5., 1),
+                branch(WaveValue::Time, WaveComparison::Greater, 60., 1),
+                WaveInstruction::Goto(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_branches_jump_between_loops() {
+        let instructions = parse(
+            "
+            label outer
+            wait 1
+            label inner
+            spawn 0,0
+            if score < 10 goto inner
+            if time < 60 goto outer
+            label done
+            end
+            ",
+        );
+
+        assert_eq!(instructions.len(), 5);
+        assert!(matches!(
+            instructions[2],
+            WaveInstruction::If { target: 1, .. }
+        ));
+        assert!(matches!(
+            instructions[3],
+            WaveInstruction::If { target: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(
+            error("random\n\njump"),
+            "line 3: unknown instruction 'jump'"
+        );
+        assert_eq!(error("position left"), "line 1: invalid position 'left'");
+        assert_eq!(error("spawn"), "line 1: spawn needs at least one offset");
+        assert_eq!(error("spawn 1"), "line 1: missing offset y");
+        assert_eq!(error("pattern"), "line 1: missing pattern");
+        assert_eq!(error("pattern -1"), "line 1: invalid pattern '-1'");
+        assert_eq!(error("wait soon"), "line 1: invalid wait 'soon'");
+        assert_eq!(error("random 2"), "line 1: unexpected '2'");
+        assert_eq!(error("goto nowhere"), "line 1: unknown label 'nowhere'");
+        assert_eq!(error("goto"), "line 1: missing label");
+        assert_eq!(error("label"), "line 1: missing label name");
+        assert_eq!(error("label a\nlabel a"), "line 2: duplicate label 'a'");
+        assert_eq!(error("music"), "line 1: missing music intensity");
+    }
+
+    #[test]
+    fn rejects_malformed_branches() {
+        let error = |branch: &str| error(&format!("label top\n{}", branch));
+        assert_eq!(
+            error("if lives < 3 goto top"),
+            "line 2: can't branch on 'lives'"
+        );
+        assert_eq!(
+            error("if score = 3 goto top"),
+            "line 2: unknown comparison '='"
+        );
+        assert_eq!(
+            error("if score < goto top"),
+            "line 2: invalid threshold 'goto'"
+        );
+        assert_eq!(
+            error("if score < 3 top"),
+            "line 2: expected 'goto' after the condition"
+        );
+        assert_eq!(error("if score < 3 goto"), "line 2: missing label");
+        assert_eq!(
+            error("if score < 3 goto top top"),
+            "line 2: unexpected 'top'"
+        );
+    }
+
+    /// Runs a frame of the script, returning the instructions passed on to the game
+    fn run_frame(runner: &mut WaveRunner, script: &WaveScript, delta: f32) -> Vec<WaveInstruction> {
+        let mut handled = vec![];
+        runner.run(
+            script,
+            delta,
+            2.,
+            |_| 0.,
+            |_, instruction| {
+                handled.push(instruction.clone());
+                true
+            },
+        );
+        handled
+    }
+
+    #[test]
+    fn runner_stops_a_loop_without_a_wait_each_frame() {
+        let script = parse_wave_script("label loop\nspawn 0,0\ngoto loop").unwrap();
+        let mut runner = WaveRunner::default();
+
+        // every other instruction is a spawn
+        for _ in 0..3 {
+            let handled = run_frame(&mut runner, &script, 0.1);
+            assert_eq!(handled.len(), MAX_WAVE_INSTRUCTIONS_PER_FRAME / 2);
+        }
+    }
+
+    #[test]
+    fn runner_waits_between_instructions() {
+        let script =
+            parse_wave_script("label loop\nspawn 0,0\nwait 1\nspawn 1,0\nwait interval\ngoto loop")
+                .unwrap();
+        let mut runner = WaveRunner::default();
+
+        assert_eq!(run_frame(&mut runner, &script, 0.).len(), 1);
+        assert_eq!(run_frame(&mut runner, &script, 0.5).len(), 0);
+        assert_eq!(run_frame(&mut runner, &script, 0.5).len(), 1);
+
+        // the spawn interval passed to the runner is 2 seconds
+        assert_eq!(run_frame(&mut runner, &script, 1.5).len(), 0);
+        assert_eq!(run_frame(&mut runner, &script, 0.5).len(), 1);
+    }
+
+    #[test]
+    fn runner_stops_at_the_end() {
+        let script = parse_wave_script("spawn 0,0\nend\nspawn 1,0").unwrap();
+        let mut runner = WaveRunner::default();
+
+        assert_eq!(run_frame(&mut runner, &script, 0.).len(), 1);
+        assert_eq!(run_frame(&mut runner, &script, 10.).len(), 0);
+    }
+}
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    game_time::GameTime,
+    obstacles::{ObstacleCrossedEvent, ObstacleOutcome},
+    player::{Laser, TetherSegment},
+    score::CAPTURE_SCORE,
+    tether::Tethers,
+    GameState,
+};
+
+/// How many bits of hay burst out of each captured bale
+const HAY_BURST_COUNT: usize = 12;
+
+/// The fastest a bit of hay is thrown out, in world units per second
+const HAY_BURST_SPEED: f32 = 180.;
+
+/// How long the bits of hay last, in game seconds
+const HAY_LIFETIME: f32 = 0.7;
+
+/// Downwards acceleration on the bits of hay, in world units per second squared
+const HAY_GRAVITY: f32 = 400.;
+
+/// How fast the score popups rise, in world units per second, and how long they last in
+/// game seconds
+const POPUP_RISE_SPEED: f32 = 60.;
+const POPUP_LIFETIME: f32 = 1.;
+
+/// How long the tether that caught a bale flashes for, in game seconds
+const TETHER_FLASH_TIME: f32 = 0.25;
+
+/// Particles are drawn in front of the tractors and obstacles
+const PARTICLE_Z: f32 = 5.;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ParticleMaterials>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(spawn_capture_effects.system())
+                    .with_system(update_particles.system())
+                    .with_system(fade_score_popups.system())
+                    .with_system(flash_tethers.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_particles.system()),
+            );
+    }
+}
+
+/// The colors the bits of hay are randomly picked from
+struct ParticleMaterials {
+    hay: Vec<Handle<ColorMaterial>>,
+}
+
+impl FromWorld for ParticleMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ParticleMaterials {
+            hay: vec![
+                materials.add(Color::rgb(0.93, 0.8, 0.4).into()),
+                materials.add(Color::rgb(0.85, 0.68, 0.3).into()),
+                materials.add(Color::rgb(0.75, 0.6, 0.25).into()),
+            ],
+        }
+    }
+}
+
+/// Something that moves on its own and disappears after a while, ageing with the game time
+struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+    age: f32,
+    lifetime: f32,
+
+    /// Whether the particle shrinks away as it ages
+    shrinks: bool,
+}
+
+impl Particle {
+    /// How far through its life the particle is, from 0 (just spawned) to 1 (gone)
+    fn progress(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+}
+
+/// Floating text showing the points scored for a capture
+struct ScorePopup;
+
+/// Briefly lights up a segment of a tether, fading back to its usual tint
+struct TetherFlash {
+    remaining: f32,
+    tint: Color,
+}
+
+/// Pops up the points, bursts the bale into bits of hay and flashes the tether whenever
+/// hay is captured
+fn spawn_capture_effects(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    materials: Res<ParticleMaterials>,
+    tethers: Option<Res<Tethers>>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+    segments: Query<
+        (
+            Entity,
+            &TetherSegment,
+            &TextureAtlasSprite,
+            Option<&TetherFlash>,
+        ),
+        With<Laser>,
+    >,
+) {
+    let mut rng = thread_rng();
+
+    for ev in crossed_events.iter() {
+        if ev.outcome != ObstacleOutcome::Captured {
+            continue;
+        }
+        let position = ev.position.extend(PARTICLE_Z);
+
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    format!("+{:.0}", CAPTURE_SCORE),
+                    TextStyle {
+                        font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                        font_size: 28.0,
+                        color: Color::rgb(0.95, 0.85, 0.3),
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                transform: Transform::from_translation(position),
+                ..Default::default()
+            })
+            .insert(ScorePopup)
+            .insert(Particle {
+                velocity: Vec2::new(0., POPUP_RISE_SPEED),
+                gravity: 0.,
+                age: 0.,
+                lifetime: POPUP_LIFETIME,
+                shrinks: false,
+            });
+
+        for _ in 0..HAY_BURST_COUNT {
+            let angle = rng.gen_range(0.0..std::f32::consts::PI);
+            let speed = rng.gen_range(0.3..1.) * HAY_BURST_SPEED;
+            let size = rng.gen_range(3.0..6.);
+            let material = materials.hay[rng.gen_range(0..materials.hay.len())].clone();
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material,
+                    sprite: Sprite::new(Vec2::new(size, size)),
+                    transform: Transform {
+                        translation: position,
+                        rotation: Quat::from_rotation_z(rng.gen_range(0.0..std::f32::consts::PI)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(Particle {
+                    velocity: speed * Vec2::new(angle.cos(), angle.sin()),
+                    gravity: HAY_GRAVITY,
+                    age: 0.,
+                    lifetime: HAY_LIFETIME * rng.gen_range(0.7..1.),
+                    shrinks: true,
+                });
+        }
+
+        // flash the tether that caught the bale
+        let tether = tethers
+            .as_ref()
+            .and_then(|tethers| tethers.0.iter().position(|t| t.spans(ev.position.x)));
+        if let Some(tether) = tether {
+            for (entity, segment, sprite, flash) in segments.iter() {
+                if segment.tether != tether {
+                    continue;
+                }
+                // keep the original tint if the tether is already flashing
+                let tint = flash.map_or(sprite.color, |flash| flash.tint);
+                commands.entity(entity).insert(TetherFlash {
+                    remaining: TETHER_FLASH_TIME,
+                    tint,
+                });
+            }
+        }
+    }
+}
+
+/// Moves, ages and despawns particles
+fn update_particles(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut particles: Query<(Entity, &mut Transform, &mut Particle)>,
+) {
+    for (entity, mut tx, mut particle) in particles.iter_mut() {
+        particle.age += time.delta;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity * time.delta;
+        tx.translation.x += particle.velocity.x * time.delta;
+        tx.translation.y += particle.velocity.y * time.delta;
+
+        if particle.shrinks {
+            tx.scale = Vec3::splat(1. - particle.progress());
+        }
+    }
+}
+
+/// Fades the score popups out over the second half of their life
+fn fade_score_popups(mut popups: Query<(&mut Text, &Particle), With<ScorePopup>>) {
+    for (mut text, particle) in popups.iter_mut() {
+        let alpha = (2. * (1. - particle.progress())).min(1.);
+        text.sections[0].style.color.set_a(alpha);
+    }
+}
+
+/// Fades flashing tether segments from white back to their tint
+fn flash_tethers(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut flashes: Query<(Entity, &mut TextureAtlasSprite, &mut TetherFlash)>,
+) {
+    for (entity, mut sprite, mut flash) in flashes.iter_mut() {
+        flash.remaining -= time.delta;
+        if flash.remaining <= 0. {
+            sprite.color = flash.tint;
+            commands.entity(entity).remove::<TetherFlash>();
+            continue;
+        }
+
+        let brightness = flash.remaining / TETHER_FLASH_TIME;
+        let tint = Vec4::from(flash.tint);
+        sprite.color = tint.lerp(Vec4::splat(1.), brightness).into();
+    }
+}
+
+fn despawn_particles(mut commands: Commands, particles: Query<Entity, With<Particle>>) {
+    for particle in particles.iter() {
+        commands.entity(particle).despawn_recursive();
+    }
+}
@@ -1,94 +1,282 @@
 use bevy::prelude::*;
 
 use crate::actions::Actions;
+use crate::audio_events::{AudioEffect, PlayAudioEffectEvent};
+use crate::difficulty::Difficulty;
 use crate::game_map::GameMap;
 use crate::game_time::GameTime;
-use crate::loading::TextureAssets;
+use crate::obstacles::{ObstacleCrossedEvent, ObstacleOutcome};
+use crate::practice::PracticeMode;
+use crate::profile::Profile;
+use crate::shop::Upgrade;
+use crate::skins::Skins;
+use crate::tether::{Tether, Tethers, HAY_WEIGHT, TETHER_SEGMENTS};
+use crate::tutorial::Tutorial;
 use crate::GameState;
 use crate::SystemLabels;
 
-#[macro_export]
-macro_rules! by_side {
-    ($side:expr, $left:expr, $right:expr) => {{
-        match $side {
-            PlayerShipSide::Left => $left,
-            PlayerShipSide::Right => $right,
-        }
-    }};
-}
+/// The most tractors a run can have, one for each group of driving keys
+pub const MAX_TRACTORS: usize = 3;
+
+/// The default speed of each tractor
+pub const PLAYER_SPEED: f32 = 150.;
+
+/// Tractors move faster than their speed so the controls feel responsive
+pub const PLAYER_MOVE_SCALE: f32 = 1.5;
 
+/// The strain the tether can take before breaking on Normal difficulty
 pub const MAX_SEPARATION_STRAIN: f32 = 7.;
 
+/// The length of each tether in sprites, before any upgrades
+pub const TETHER_LENGTH: f32 = 5.;
+
+/// Tractors drive up and down the lane slower than they move across it
+pub const PLAYER_LIFT_SCALE: f32 = 0.6;
+
+/// How fast each tractor moves along the tether while snapping it in or out
+const TETHER_SNAP_SPEED: f32 = 600.;
+
+/// How long a tether snap lasts, in seconds
+const TETHER_SNAP_DURATION: f32 = 0.2;
+
+/// Seconds before the tether can be snapped again for free
+const TETHER_SNAP_COOLDOWN: f32 = 4.;
+
+/// Strain added by snapping the tether while it is still cooling down
+const TETHER_SNAP_STRAIN_COST: f32 = 1.5;
+
+/// Snapping the tether in stops once the tractors are this close, in sprites
+const TETHER_SNAP_MIN_SEPARATION: f32 = 1.5;
+
+/// The strains at which the tether starts to strain, strains badly and is about to break,
+/// as fractions of the strain that breaks it. The tether's flicker, creaks, warning beeps,
+/// rumble and music all step up at these.
+pub const STRAIN_THRESHOLDS: [f32; 3] = [0.33, 0.66, 0.9];
+
+/// The strains at which the tether flickers faster. The HUD's strain gauge marks them.
+pub const STRAIN_FLICKER_THRESHOLDS: [f32; 2] = [STRAIN_THRESHOLDS[0], STRAIN_THRESHOLDS[1]];
+
 pub struct PlayerPlugin;
 
 pub struct Player;
 
-pub struct IsDead(pub String);
+/// Marks the player as dead, with why and where in the world the run was lost
+pub struct IsDead(pub DeathCause, pub Vec2);
+
+/// Why a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeathCause {
+    TractorsCollided,
+    TetherBroke,
+    HitObstacle,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 3] = [
+        DeathCause::TractorsCollided,
+        DeathCause::TetherBroke,
+        DeathCause::HitObstacle,
+    ];
+
+    /// The id the cause is saved and exported under
+    pub fn id(&self) -> &'static str {
+        match self {
+            DeathCause::TractorsCollided => "tractors_collided",
+            DeathCause::TetherBroke => "tether_broke",
+            DeathCause::HitObstacle => "hit_obstacle",
+        }
+    }
+
+    /// The message shown when a run ends this way
+    pub fn message(&self) -> &'static str {
+        match self {
+            DeathCause::TractorsCollided => "The tractors collided!",
+            DeathCause::TetherBroke => "The tether broke!",
+            DeathCause::HitObstacle => "A tractor hit an obstacle!",
+        }
+    }
+}
 
 pub struct Laser;
 
-#[derive(Debug, Copy, Clone)]
+/// One segment of the laser tethers, drawn between two points of a Tether
+pub struct TetherSegment {
+    pub tether: usize,
+    pub segment: usize,
+}
+
+/// How many frames a tractor or tether sprite animates through, set by the skin
+pub struct AnimationFrames(pub usize);
+
+/// How many tractors the next run is played with, chosen in the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TractorCount(pub usize);
+
+impl Default for TractorCount {
+    fn default() -> Self {
+        TractorCount(2)
+    }
+}
+
+impl TractorCount {
+    /// The next tractor count to pick in the menu, wrapping around
+    pub fn next(&self) -> Self {
+        if self.0 >= MAX_TRACTORS {
+            TractorCount(2)
+        } else {
+            TractorCount(self.0 + 1)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct PlayerShip {
     pub is_dead: bool,
     pub speed: f32,
 
+    /// The length of each tether
     pub max_separation: f32,
-    pub separation_strain: f32,
+
+    /// The strain on each tether, from the leftmost pair of tractors
+    pub separation_strains: Vec<f32>,
+    pub max_separation_strain: f32,
+    pub strain_recovery_rate: f32,
+
+    /// Seconds until the tether can be snapped again without adding strain
+    pub snap_cooldown: f32,
+
+    /// Seconds left of the current snap, and whether it pulls in (-1) or pushes out (1)
+    pub snap_time_left: f32,
+    pub snap_direction: i8,
+
+    /// Obstacle hits that can be shrugged off before a tractor is wrecked
+    pub shields: u32,
 }
 
-pub enum PlayerShipSide {
-    Left,
-    Right,
+impl PlayerShip {
+    /// The strain on the most strained tether as a fraction of the strain that breaks it
+    pub fn strain_ratio(&self) -> f32 {
+        (0..self.separation_strains.len())
+            .map(|idx| self.tether_strain_ratio(idx))
+            .fold(0., f32::max)
+    }
+
+    /// The index of the tether under the most strain
+    pub fn most_strained_tether(&self) -> usize {
+        (0..self.separation_strains.len())
+            .max_by(|a, b| {
+                self.separation_strains[*a]
+                    .partial_cmp(&self.separation_strains[*b])
+                    .unwrap()
+            })
+            .unwrap_or(0)
+    }
+
+    /// The strain on one tether as a fraction of the strain that breaks it
+    pub fn tether_strain_ratio(&self, tether: usize) -> f32 {
+        self.separation_strains[tether] / self.max_separation_strain
+    }
+}
+
+/// Which tractor this is, counting from 0 for the leftmost
+pub struct PlayerShipSide(pub usize);
+
+/// The positions of the tractors, ordered by their PlayerShipSide
+pub fn tractor_positions(sides: impl Iterator<Item = (usize, Vec2)>) -> Vec<Vec2> {
+    let mut sides = sides.collect::<Vec<_>>();
+    sides.sort_by_key(|(idx, _)| *idx);
+    sides.into_iter().map(|(_, position)| position).collect()
+}
+
+/// Where the first neighbouring pair of tractors collide, given their positions ordered by
+/// side. Tractors can't pass each other at any height in the lane, so each has to stay at
+/// least `collision_distance` to the right of the one before it.
+fn tractor_collision(positions: &[Vec2], collision_distance: f32) -> Option<Vec2> {
+    positions
+        .windows(2)
+        .find(|pair| pair[1].x - pair[0].x < collision_distance)
+        .map(|pair| (pair[0] + pair[1]) / 2.)
+}
+
+/// The x position halfway along a tether, given the tractor positions ordered by side
+pub fn tether_middle_x(positions: &[Vec2], tether: usize) -> f32 {
+    (positions[tether].x + positions[tether + 1].x) / 2.
 }
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Playing)
-                .with_system(spawn_player.system())
-                .with_system(spawn_camera.system()),
-        )
-        .add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(
-                    move_player
-                        .system()
-                        .label(SystemLabels::MovePlayer)
-                        .after(SystemLabels::UpdateTime),
-                )
-                .with_system(
-                    is_player_dead_checks
-                        .system()
-                        .after(SystemLabels::MovePlayer),
-                )
-                .with_system(animate_player.system())
-                .with_system(update_laser.system().after(SystemLabels::MovePlayer)),
-        )
-        .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(despawn_level.system()));
+        app.init_resource::<TractorCount>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_player.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        move_player
+                            .system()
+                            .label(SystemLabels::MovePlayer)
+                            .after(SystemLabels::UpdateTime),
+                    )
+                    .with_system(
+                        is_player_dead_checks
+                            .system()
+                            .after(SystemLabels::MovePlayer),
+                    )
+                    .with_system(animate_player.system())
+                    .with_system(update_laser.system().after(SystemLabels::MovePlayer)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_level.system()),
+            );
     }
 }
 
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
-}
-
 fn spawn_player(
     mut commands: Commands,
-    textures: Res<TextureAssets>,
+    asset_server: Res<AssetServer>,
+    skins: Res<Skins>,
     game_map: Res<GameMap>,
+    difficulty: Res<Difficulty>,
+    tractor_count: Res<TractorCount>,
+    tutorial: Res<Tutorial>,
+    profile: Res<Profile>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
-    println!("Spawning player");
+    // the tutorial prompts are written for two tractors
+    let count = if tutorial.enabled { 2 } else { tractor_count.0 };
+    println!("Spawning player with {} tractors", count);
 
+    // upgrades bought in the shop apply to every run
     let ship = PlayerShip {
         is_dead: false,
-        speed: 150.,
+        speed: PLAYER_SPEED * profile.effect(Upgrade::Speed),
 
-        max_separation: 5. * game_map.sprite_size,
-        separation_strain: 0.,
+        max_separation: (TETHER_LENGTH + profile.effect(Upgrade::TetherLength))
+            * game_map.sprite_size,
+        separation_strains: vec![0.; count - 1],
+        max_separation_strain: difficulty.max_separation_strain,
+        strain_recovery_rate: difficulty.strain_recovery_rate
+            * profile.effect(Upgrade::StrainRecovery),
+
+        snap_cooldown: 0.,
+        snap_time_left: 0.,
+        snap_direction: 0,
+
+        shields: profile.level(Upgrade::Shield),
     };
 
-    commands.insert_resource(ship);
+    // the tractors start a sprite apart, centered on the map
+    let middle = (count - 1) as f32 / 2.;
+    let positions = (0..count)
+        .map(|idx| {
+            Vec2::new(
+                (idx as f32 - middle) * game_map.sprite_size,
+                game_map.bottom_y(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let skin = skins.selected(&profile);
 
     // spawn the player + tractors
     commands
@@ -97,60 +285,64 @@ fn spawn_player(
         .insert(GlobalTransform::from_translation(Vec3::ZERO))
         .insert(Player)
         .with_children(|parent| {
-            let texture_atlas =
-                TextureAtlas::from_grid(textures.player_left.clone(), Vec2::new(32., 32.0), 4, 1);
-            let texture_atlas_handle = texture_atlases.add(texture_atlas);
-
-            parent
-                .spawn_bundle({
-                    SpriteSheetBundle {
-                        texture_atlas: texture_atlas_handle,
-                        transform: Transform::from_translation(Vec3::new(
-                            -game_map.sprite_size / 2.,
-                            game_map.bottom_y(),
-                            1.,
-                        )),
-                        ..Default::default()
-                    }
-                })
-                .insert(PlayerShipSide::Left);
-
-            let texture_atlas =
-                TextureAtlas::from_grid(textures.player_right.clone(), Vec2::new(32., 32.0), 4, 1);
-            let texture_atlas_handle = texture_atlases.add(texture_atlas);
-
-            parent
-                .spawn_bundle({
-                    SpriteSheetBundle {
-                        texture_atlas: texture_atlas_handle,
-                        transform: Transform::from_translation(Vec3::new(
-                            game_map.sprite_size / 2.,
-                            game_map.bottom_y(),
-                            1.,
-                        )),
-                        ..Default::default()
-                    }
-                })
-                .insert(PlayerShipSide::Right);
+            for (idx, position) in positions.iter().enumerate() {
+                // the rightmost tractor faces the others
+                let atlas = if idx + 1 == count {
+                    &skin.tractor_right
+                } else {
+                    &skin.tractor_left
+                };
+                let texture_atlas_handle = texture_atlases.add(atlas.texture_atlas(&asset_server));
+
+                parent
+                    .spawn_bundle({
+                        SpriteSheetBundle {
+                            texture_atlas: texture_atlas_handle,
+                            sprite: TextureAtlasSprite {
+                                color: skin.tractor_tint,
+                                ..Default::default()
+                            },
+                            transform: Transform::from_translation(position.extend(1.)),
+                            ..Default::default()
+                        }
+                    })
+                    .insert(PlayerShipSide(idx))
+                    .insert(AnimationFrames(atlas.frames));
+            }
         });
 
-    // spawn the laser texture atlas
-    let texture_atlas =
-        TextureAtlas::from_grid(textures.laser.clone(), Vec2::new(32., 16.0), 10, 1);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
+    // each tether starts straight between its two ships
+    commands.insert_resource(Tethers(
+        positions
+            .windows(2)
+            .map(|pair| Tether::new(pair[0], pair[1], ship.max_separation))
+            .collect(),
+    ));
 
-    // spawn the laser between the two ships
-    let mut laser_tx = Transform::from_translation(Vec3::new(0., game_map.bottom_y(), 0.5));
-    laser_tx.scale.x = 1.4;
+    commands.insert_resource(ship);
 
-    commands
-        .spawn_bundle(SpriteSheetBundle {
-            texture_atlas: texture_atlas_handle,
-            transform: laser_tx,
-            ..Default::default()
-        })
-        .insert(Laser)
-        .insert(Timer::from_seconds(0.1, true));
+    // spawn the laser texture atlas
+    let texture_atlas_handle = texture_atlases.add(skin.tether.texture_atlas(&asset_server));
+
+    // spawn a laser segment for each part of each tether, positioned in update_laser
+    for tether in 0..count - 1 {
+        for segment in 0..TETHER_SEGMENTS {
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: texture_atlas_handle.clone(),
+                    sprite: TextureAtlasSprite {
+                        color: skin.tether_tint,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(0., game_map.bottom_y(), 0.5)),
+                    ..Default::default()
+                })
+                .insert(Laser)
+                .insert(TetherSegment { tether, segment })
+                .insert(AnimationFrames(skin.tether.frames))
+                .insert(Timer::from_seconds(0.1, true));
+        }
+    }
 }
 
 /// Moves a player based on input towards their target position
@@ -158,7 +350,7 @@ fn move_player(
     time: Res<GameTime>,
     actions: Res<Actions>,
     game_map: Res<GameMap>,
-    ship: Res<PlayerShip>,
+    mut ship: ResMut<PlayerShip>,
     mut ship_sides: Query<(&mut Transform, &PlayerShipSide)>,
 ) {
     // if we don't have a player, don't move
@@ -167,74 +359,177 @@ fn move_player(
     }
 
     // remove the multiplier from delta_move so the ship doesn't get faster over time
-    let delta_move = ship.speed * 1.5 * (time.delta / time.multiplier);
+    let real_delta = time.delta / time.multiplier;
+    let delta_move = ship.speed * PLAYER_MOVE_SCALE * real_delta;
 
     // calculate movement
-    let moves = (actions.player_left_move, actions.player_right_move);
-    let sides = get_ship_sides(&mut ship_sides);
-    let x_bound = game_map.get_x_bound();
-    let target_x = (
-        (sides.0.x + (moves.0 as f32) * delta_move).clamp(-x_bound, x_bound),
-        (sides.1.x + (moves.1 as f32) * delta_move).clamp(-x_bound, x_bound),
+    let positions = tractor_positions(
+        ship_sides
+            .iter_mut()
+            .map(|(tx, side)| (side.0, tx.translation.truncate())),
     );
+    let count = positions.len();
+    let x_bound = game_map.get_x_bound();
 
-    let rotations = (
-        if moves.0 == 0 {
-            0.
-        } else {
-            moves.0.signum() as f32 * -std::f32::consts::FRAC_PI_8
-        },
-        if moves.1 == 0 {
-            0.
-        } else {
-            moves.1.signum() as f32 * -std::f32::consts::FRAC_PI_8
-        },
-    );
+    // tractors can also drive up and down within the lane at the bottom of the map
+    let delta_lift = delta_move * PLAYER_LIFT_SCALE;
+    let (min_y, max_y) = (game_map.bottom_y(), game_map.lane_top_y());
+
+    let mut targets = positions
+        .iter()
+        .enumerate()
+        .map(|(idx, position)| {
+            Vec2::new(
+                (position.x + (actions.tractor_moves[idx] as f32) * delta_move)
+                    .clamp(-x_bound, x_bound),
+                (position.y + (actions.tractor_lifts[idx] as f32) * delta_lift).clamp(min_y, max_y),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // snap the tractors together or apart along the tethers
+    let snap_move = update_tether_snap(&mut ship, &actions, real_delta);
+    if snap_move != 0. && count > 1 {
+        let center = (targets[0].x + targets[count - 1].x) / 2.;
+        let middle = (count - 1) as f32 / 2.;
+
+        for (idx, target) in targets.iter_mut().enumerate() {
+            // the outer tractors move the full snap distance, any middle one stays put
+            let share = (idx as f32 - middle) / middle;
+            if share == 0. {
+                continue;
+            }
+
+            let direction = share.signum();
+            let distance = (target.x - center) * direction;
+            let min_distance =
+                (idx as f32 - middle).abs() * TETHER_SNAP_MIN_SEPARATION * game_map.sprite_size;
+
+            // snapping in never pulls the tractors closer than the minimum
+            let new_distance = if snap_move < 0. {
+                (distance + snap_move * share.abs()).max(distance.min(min_distance))
+            } else {
+                distance + snap_move * share.abs()
+            };
+
+            target.x = (center + new_distance * direction).clamp(-x_bound, x_bound);
+        }
+    }
+
+    // the tractors can't drive through each other, so they always stay in side order
+    for idx in 1..count {
+        targets[idx].x = targets[idx].x.max(targets[idx - 1].x);
+    }
 
     // update the ship side positions and rotations
-    // TODO could make this a bit nicer with a macro
     for (mut tx, side) in ship_sides.iter_mut() {
-        tx.translation.x = by_side!(side, target_x.0, target_x.1);
-        tx.rotation = Quat::from_axis_angle(Vec3::Z, by_side!(side, rotations.0, rotations.1));
+        let target = targets[side.0];
+        let rotation = match actions.tractor_moves[side.0] {
+            0 => 0.,
+            moving => moving.signum() as f32 * -std::f32::consts::FRAC_PI_8,
+        };
+
+        tx.translation.x = target.x;
+        tx.translation.y = target.y;
+        tx.rotation = Quat::from_axis_angle(Vec3::Z, rotation);
     }
 }
 
-/// Gest the ship sides from the PlayerShipSide query
-fn get_ship_sides(ship_sides: &mut Query<(&mut Transform, &PlayerShipSide)>) -> (Vec3, Vec3) {
-    ship_sides
-        .iter_mut()
-        .fold((Vec3::ZERO, Vec3::ZERO), |acc, (tx, side)| {
-            by_side!(side, (tx.translation, acc.1), (acc.0, tx.translation))
-        })
+/// Starts a tether snap if one was requested, and returns how far each tractor should
+/// move along the tether this frame (negative pulls them together)
+fn update_tether_snap(ship: &mut PlayerShip, actions: &Actions, real_delta: f32) -> f32 {
+    ship.snap_cooldown = (ship.snap_cooldown - real_delta).max(0.);
+
+    let direction = actions.tether_snap_out as i8 - actions.tether_snap_in as i8;
+    if ship.snap_time_left <= 0. && direction != 0 {
+        // snapping again before the cooldown is up strains the tether instead
+        if ship.snap_cooldown > 0. {
+            for strain in ship.separation_strains.iter_mut() {
+                *strain += TETHER_SNAP_STRAIN_COST;
+            }
+        } else {
+            ship.snap_cooldown = TETHER_SNAP_COOLDOWN;
+        }
+
+        ship.snap_time_left = TETHER_SNAP_DURATION;
+        ship.snap_direction = direction;
+    }
+
+    if ship.snap_time_left <= 0. {
+        return 0.;
+    }
+
+    let snap_delta = real_delta.min(ship.snap_time_left);
+    ship.snap_time_left -= real_delta;
+    ship.snap_direction as f32 * TETHER_SNAP_SPEED * snap_delta
 }
 
 /// check if a player is ded
 pub fn is_player_dead_checks(
     mut commands: Commands,
+    mut audio_events: EventWriter<PlayAudioEffectEvent>,
     game_map: Res<GameMap>,
+    difficulty: Res<Difficulty>,
     mut ship: ResMut<PlayerShip>,
+    mut practice: ResMut<PracticeMode>,
     players: Query<Entity, (With<Player>, Without<IsDead>)>,
-    ship_side_tx_query: Query<&Transform, With<PlayerShipSide>>,
+    ship_side_tx_query: Query<(&Transform, &PlayerShipSide)>,
 ) {
     match players.single() {
         Ok(player) => {
-            // first check if the players bash into each other
-            let diff = ship_side_tx_query
+            // first check if any of the players bash into each other, and where
+            let positions = tractor_positions(
+                ship_side_tx_query
+                    .iter()
+                    .map(|(tx, side)| (side.0, tx.translation.truncate())),
+            );
+            let collision_distance = game_map.sprite_size * difficulty.tractor_collision_distance;
+            let collision = tractor_collision(&positions, collision_distance);
+            let touching = collision.is_some();
+
+            let max_strain = ship.max_separation_strain;
+            let broken_tether = ship
+                .separation_strains
                 .iter()
-                .fold(0., |acc, tx| tx.translation.x - acc);
+                .position(|strain| *strain > max_strain);
+
+            // in practice mode failures are recorded but don't end the run
+            if practice.enabled {
+                if let (Some(point), false) = (collision, practice.tractors_touching) {
+                    println!("Bashed into each other!");
+                    practice.tractor_collisions += 1;
+                    audio_events.send(PlayAudioEffectEvent::at(AudioEffect::Crash, point.x));
+                }
+                practice.tractors_touching = touching;
+
+                if let Some(tether) = broken_tether {
+                    println!("Tether broke!");
+                    practice.tether_breaks += 1;
+                    ship.separation_strains[tether] = 0.;
+                    audio_events.send(PlayAudioEffectEvent::at(
+                        AudioEffect::TetherSnap,
+                        tether_middle_x(&positions, tether),
+                    ));
+                }
 
-            if diff.abs() < game_map.sprite_size * 0.75 {
+                return;
+            }
+
+            if let Some(point) = collision {
                 println!("Bashed into each other!");
+                audio_events.send(PlayAudioEffectEvent::at(AudioEffect::Crash, point.x));
                 ship.is_dead = true;
                 commands
                     .entity(player)
-                    .insert(IsDead("The tractors collided!".into()));
-            } else if ship.separation_strain > MAX_SEPARATION_STRAIN {
+                    .insert(IsDead(DeathCause::TractorsCollided, point));
+            } else if let Some(tether) = broken_tether {
                 println!("Tether broke!");
+                let point = (positions[tether] + positions[tether + 1]) / 2.;
+                audio_events.send(PlayAudioEffectEvent::at(AudioEffect::TetherSnap, point.x));
                 ship.is_dead = true;
                 commands
                     .entity(player)
-                    .insert(IsDead("The tether broke!".into()));
+                    .insert(IsDead(DeathCause::TetherBroke, point));
             }
         }
         Err(_) => {}
@@ -244,63 +539,95 @@ pub fn is_player_dead_checks(
 /// Animates the player sprites
 fn animate_player(
     game_time: Res<GameTime>,
-    mut sprites: Query<&mut TextureAtlasSprite, With<PlayerShipSide>>,
+    mut sprites: Query<(&mut TextureAtlasSprite, &AnimationFrames), With<PlayerShipSide>>,
 ) {
     if !game_time.fixed_update {
         return;
     }
 
-    for mut sprite in sprites.iter_mut() {
-        sprite.index = (sprite.index + 1) % 4;
+    for (mut sprite, frames) in sprites.iter_mut() {
+        sprite.index = (sprite.index + 1) % frames.0;
     }
 }
 
-/// Draws and animates "laser" between the two ships
+/// Simulates the tethers between neighbouring ships, and draws and animates them as "lasers"
 fn update_laser(
     time: Res<GameTime>,
     game_map: Res<GameMap>,
     mut ship: ResMut<PlayerShip>,
-    mut lasers: Query<(&mut Transform, &mut TextureAtlasSprite, &mut Timer), With<Laser>>,
-    mut ship_sides: Query<(&Transform, &PlayerShipSide), Without<Laser>>,
+    mut tethers: ResMut<Tethers>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+    mut lasers: Query<
+        (
+            &mut Transform,
+            &mut TextureAtlasSprite,
+            &mut Timer,
+            &TetherSegment,
+            &AnimationFrames,
+        ),
+        With<Laser>,
+    >,
+    ship_sides: Query<(&Transform, &PlayerShipSide), Without<Laser>>,
 ) {
     if ship.is_dead {
         return;
     }
 
-    let sides = ship_sides
-        .iter_mut()
-        .fold((Vec3::ZERO, Vec3::ZERO), |acc, (tx, side)| {
-            by_side!(side, (tx.translation, acc.1), (acc.0, tx.translation))
-        });
+    let positions = tractor_positions(
+        ship_sides
+            .iter()
+            .map(|(tx, side)| (side.0, tx.translation.truncate())),
+    );
 
-    // stretch the laser to fit between the two sides
-    let dx = sides.1.x - sides.0.x;
-    let x_scale = 0.4 + dx / game_map.sprite_size;
-
-    // update separation strain
-    if dx > ship.max_separation {
-        // increase strain
-        ship.separation_strain += time.delta;
-    } else {
-        // reduce strain
-        if ship.separation_strain > 0. {
-            ship.separation_strain = (ship.separation_strain - 0.75 * time.delta).max(0.);
+    // captured hay hangs off the tether it was caught by until it settles
+    for ev in crossed_events.iter() {
+        if ev.outcome == ObstacleOutcome::Captured {
+            if let Some(tether) = tethers.0.iter_mut().find(|t| t.spans(ev.position.x)) {
+                tether.add_load(ev.position.x, HAY_WEIGHT);
+            }
         }
     }
 
-    for (mut laser, mut sprite, mut timer) in lasers.iter_mut() {
-        // reposition the laser
-        laser.scale.x = x_scale;
-        laser.translation.x = sides.0.x + dx / 2.;
+    let recovery_rate = ship.strain_recovery_rate;
+    for (idx, tether) in tethers.0.iter_mut().enumerate() {
+        tether.update(positions[idx], positions[idx + 1], time.delta);
 
-        // update the animation frame for the laser
-        // show flickering if stress > 0.5
+        // update separation strain from the tension in the tether
+        let strain_rate = tether.strain_rate();
+        let strain = &mut ship.separation_strains[idx];
+        if strain_rate > 0. {
+            // increase strain
+            *strain += strain_rate * time.delta;
+        } else {
+            // reduce strain
+            *strain = (*strain - recovery_rate * time.delta).max(0.);
+        }
+    }
+
+    for (mut laser, mut sprite, mut timer, segment, frames) in lasers.iter_mut() {
+        // stretch the segment between its two points, overlapping a little to hide the joins
+        let tether = &tethers.0[segment.tether];
+        let points = tether.points();
+        let start = points[segment.segment];
+        let end = points[segment.segment + 1];
+        let offset = end - start;
+        let midpoint = start + offset / 2.;
+
+        laser.translation.x = midpoint.x;
+        laser.translation.y = midpoint.y;
+        laser.scale.x = 0.1 + offset.length() / game_map.sprite_size;
+        laser.rotation = Quat::from_axis_angle(Vec3::Z, offset.y.atan2(offset.x));
+
+        // update the animation frame for the laser, flickering through more of the
+        // skin's frames (out of every 10) as the strain rises
         timer.tick(time.delta_duration);
         if timer.just_finished() {
-            let frame_count = if dx > ship.max_separation {
-                if ship.separation_strain > MAX_SEPARATION_STRAIN * 0.66 {
+            let strain_ratio = ship.tether_strain_ratio(segment.tether);
+            let [flicker_faster, flicker_fastest] = STRAIN_FLICKER_THRESHOLDS;
+            let frames_per_10 = if tether.is_taut() {
+                if strain_ratio > flicker_fastest {
                     10
-                } else if ship.separation_strain > MAX_SEPARATION_STRAIN * 0.33 {
+                } else if strain_ratio > flicker_faster {
                     5
                 } else {
                     3
@@ -308,6 +635,7 @@ fn update_laser(
             } else {
                 2
             };
+            let frame_count = (frames.0 * frames_per_10 / 10).max(1);
             sprite.index = (sprite.index + 1) % frame_count;
         }
     }
@@ -327,3 +655,43 @@ fn despawn_level(
         commands.entity(laser).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tractors_collide_across_the_lane() {
+        // side by side, and one driving over the other higher up the lane
+        assert!(tractor_collision(&[Vec2::new(0., 0.), Vec2::new(10., 0.)], 24.).is_some());
+        assert!(tractor_collision(&[Vec2::new(0., 0.), Vec2::new(10., 64.)], 24.).is_some());
+        assert!(tractor_collision(&[Vec2::new(0., 64.), Vec2::new(0., 0.)], 24.).is_some());
+    }
+
+    #[test]
+    fn tractors_at_different_heights_cannot_pass() {
+        // the left tractor has ended up on the right, however far apart they are
+        assert!(tractor_collision(&[Vec2::new(50., 0.), Vec2::new(-50., 96.)], 24.).is_some());
+
+        // and the middle of three can't pass the rightmost one
+        let positions = [
+            Vec2::new(-100., 0.),
+            Vec2::new(60., 96.),
+            Vec2::new(40., 0.),
+        ];
+        assert_eq!(
+            tractor_collision(&positions, 24.),
+            Some(Vec2::new(50., 48.))
+        );
+    }
+
+    #[test]
+    fn tractors_apart_do_not_collide() {
+        let positions = [
+            Vec2::new(-100., 0.),
+            Vec2::new(0., 96.),
+            Vec2::new(100., 32.),
+        ];
+        assert!(tractor_collision(&positions, 24.).is_none());
+    }
+}
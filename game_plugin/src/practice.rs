@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions, game_time::GameTime, menu::return_to_menu,
+    obstacles::patterns::AvailableSpawnPatterns, tutorial::Tutorial, GameState,
+};
+
+/// The smallest and largest speeds the game time can be frozen at in practice mode
+const MIN_FROZEN_MULTIPLIER: f32 = 0.5;
+const MAX_FROZEN_MULTIPLIER: f32 = 4.;
+const FROZEN_MULTIPLIER_STEP: f32 = 0.25;
+
+/// Settings and counters for practice mode, where failures are recorded but don't end the run
+#[derive(Debug, Default)]
+pub struct PracticeMode {
+    pub enabled: bool,
+
+    /// When set the game speed multiplier is held at this value instead of ramping up
+    pub frozen_multiplier: Option<f32>,
+
+    /// When set only this spawn pattern (an index into AvailableSpawnPatterns) is spawned
+    pub forced_pattern: Option<usize>,
+
+    pub tether_breaks: u32,
+    pub tractor_collisions: u32,
+    pub obstacle_collisions: u32,
+
+    /// Whether the tractors were touching last frame, so a collision is only recorded once
+    pub tractors_touching: bool,
+}
+
+impl PracticeMode {
+    /// Clears the failure counters at the start of a run
+    fn reset_counters(&mut self) {
+        self.tether_breaks = 0;
+        self.tractor_collisions = 0;
+        self.obstacle_collisions = 0;
+        self.tractors_touching = false;
+    }
+}
+
+pub struct PracticeUiItem;
+pub struct PracticeText;
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<PracticeMode>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_practice_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_practice_settings.system())
+                    .with_system(update_practice_text_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_practice_ui.system()),
+            );
+    }
+}
+
+/// Spawns the practice mode status text, if practice mode is enabled
+fn spawn_practice_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tutorial: Res<Tutorial>,
+    mut practice: ResMut<PracticeMode>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !practice.enabled {
+        return;
+    }
+
+    practice.reset_counters();
+
+    // the tutorial shows its own prompts instead
+    if tutorial.enabled {
+        return;
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(PracticeUiItem)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: "".to_string(),
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.3, 0.3, 0.3),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                })
+                .insert(PracticeText);
+
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "F: freeze speed, [/]: change speed, P: force pattern, Space: quit"
+                            .to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 16.0,
+                            color: Color::rgb(0.3, 0.3, 0.3),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+        });
+}
+
+/// Applies practice mode input, freezing the speed, forcing patterns or quitting the run
+fn update_practice_settings(
+    mut actions: ResMut<Actions>,
+    game_time: Res<GameTime>,
+    patterns: Res<AvailableSpawnPatterns>,
+    tutorial: Res<Tutorial>,
+    mut practice: ResMut<PracticeMode>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if !practice.enabled {
+        return;
+    }
+
+    if return_to_menu(&mut actions, &mut state) {
+        return;
+    }
+
+    // the tutorial controls the game speed and obstacles itself
+    if tutorial.enabled {
+        return;
+    }
+
+    if actions.practice_toggle_freeze {
+        practice.frozen_multiplier = match practice.frozen_multiplier {
+            Some(_) => None,
+            None => Some(game_time.multiplier),
+        };
+    }
+
+    if actions.practice_speed_change != 0 {
+        let current = practice.frozen_multiplier.unwrap_or(game_time.multiplier);
+        practice.frozen_multiplier = Some(
+            (current + actions.practice_speed_change as f32 * FROZEN_MULTIPLIER_STEP)
+                .clamp(MIN_FROZEN_MULTIPLIER, MAX_FROZEN_MULTIPLIER),
+        );
+    }
+
+    if actions.practice_cycle_pattern {
+        // cycle through each pattern in turn, then back to random selection
+        practice.forced_pattern = match practice.forced_pattern {
+            None => Some(0),
+            Some(idx) if idx + 1 < patterns.patterns.len() => Some(idx + 1),
+            Some(_) => None,
+        };
+    }
+}
+
+/// Updates the practice mode status text
+fn update_practice_text_ui(
+    game_time: Res<GameTime>,
+    practice: Res<PracticeMode>,
+    mut practice_text: Query<&mut Text, With<PracticeText>>,
+) {
+    if !practice.enabled {
+        return;
+    }
+
+    let speed = match practice.frozen_multiplier {
+        Some(multiplier) => format!("x{:.2} (frozen)", multiplier),
+        None => format!("x{:.2}", game_time.multiplier),
+    };
+    let pattern = match practice.forced_pattern {
+        Some(idx) => format!("#{}", idx + 1),
+        None => "random".to_string(),
+    };
+
+    for mut text in practice_text.iter_mut() {
+        text.sections[0].value = format!(
+            "PRACTICE speed {}, pattern {}, tether breaks: {}, collisions: {}",
+            speed,
+            pattern,
+            practice.tether_breaks,
+            practice.tractor_collisions + practice.obstacle_collisions
+        );
+    }
+}
+
+/// despawns the practice mode ui
+fn despawn_practice_ui(mut commands: Commands, items: Query<Entity, With<PracticeUiItem>>) {
+    for ent in items.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    player::DeathCause,
+    score::{all_modes, mode_name, HighScores},
+    shop::Upgrade,
+    storage,
+};
+
+const PROFILE_FILE: &str = "profile.txt";
+
+/// Totals across every run the player has finished. Practice runs never end, so they
+/// aren't counted.
+#[derive(Debug, Default)]
+pub struct LifetimeStats {
+    pub runs: u32,
+
+    /// Real seconds played
+    pub playtime: f32,
+
+    /// All the hay ever captured, including hay that has been spent
+    pub total_hay: u32,
+
+    deaths: HashMap<DeathCause, u32>,
+}
+
+impl LifetimeStats {
+    pub fn deaths(&self, cause: DeathCause) -> u32 {
+        self.deaths.get(&cause).copied().unwrap_or(0)
+    }
+
+    /// The average length of a run in real seconds
+    pub fn average_run_length(&self) -> f32 {
+        if self.runs > 0 {
+            self.playtime / self.runs as f32
+        } else {
+            0.
+        }
+    }
+
+    /// The stats as `key=value` pairs, as saved in the profile
+    fn to_values(&self) -> Vec<(String, String)> {
+        let mut values = vec![
+            ("runs".to_string(), self.runs.to_string()),
+            ("playtime".to_string(), format!("{:.1}", self.playtime)),
+            ("total_hay".to_string(), self.total_hay.to_string()),
+            (
+                "average_run_length".to_string(),
+                format!("{:.1}", self.average_run_length()),
+            ),
+        ];
+
+        for cause in DeathCause::ALL.iter() {
+            values.push((
+                format!("deaths_{}", cause.id()),
+                self.deaths(*cause).to_string(),
+            ));
+        }
+
+        values
+    }
+
+    fn from_values(values: &HashMap<String, String>) -> Self {
+        let parse = |key: &str| values.get(key).and_then(|value| value.parse::<f32>().ok());
+
+        LifetimeStats {
+            runs: parse("runs").unwrap_or(0.) as u32,
+            playtime: parse("playtime").unwrap_or(0.),
+            total_hay: parse("total_hay").unwrap_or(0.) as u32,
+            deaths: DeathCause::ALL
+                .iter()
+                .filter_map(|cause| {
+                    Some((*cause, parse(&format!("deaths_{}", cause.id()))? as u32))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The player's hay, upgrades, skin, achievements and lifetime stats, saved between runs
+pub struct Profile {
+    /// Hay to spend in the shop, earned by capturing obstacles
+    pub hay: u32,
+
+    upgrades: HashMap<Upgrade, u32>,
+
+    /// The name of the skin picked in the menu
+    pub skin: String,
+
+    /// The ids of the achievements the player has earned
+    achievements: HashSet<String>,
+
+    pub stats: LifetimeStats,
+}
+
+impl Profile {
+    /// Loads the profile from the save file
+    pub fn load() -> Self {
+        let values = storage::load_values(PROFILE_FILE);
+
+        Profile {
+            hay: values
+                .get("hay")
+                .and_then(|hay| hay.parse().ok())
+                .unwrap_or(0),
+            upgrades: Upgrade::ALL
+                .iter()
+                .filter_map(|upgrade| {
+                    let level = values.get(upgrade.key())?.parse::<u32>().ok()?;
+                    Some((*upgrade, level.min(upgrade.max_level())))
+                })
+                .collect(),
+            skin: values.get("skin").cloned().unwrap_or_default(),
+            achievements: values
+                .get("achievements")
+                .map(|ids| {
+                    ids.split(',')
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            stats: LifetimeStats::from_values(&values),
+        }
+    }
+
+    /// Writes the profile to the save file
+    pub fn save(&self) {
+        let mut values = self
+            .upgrades
+            .iter()
+            .map(|(upgrade, level)| (upgrade.key().to_string(), level.to_string()))
+            .collect::<HashMap<_, _>>();
+        values.insert("hay".into(), self.hay.to_string());
+        values.insert("skin".into(), self.skin.clone());
+
+        let mut achievements = self.achievements.iter().cloned().collect::<Vec<_>>();
+        achievements.sort();
+        values.insert("achievements".into(), achievements.join(","));
+
+        // the average is derived, so it is only exported
+        values.extend(
+            self.stats
+                .to_values()
+                .into_iter()
+                .filter(|(key, _)| key != "average_run_length"),
+        );
+
+        storage::save_values(PROFILE_FILE, &values);
+    }
+
+    /// The level an upgrade has been bought to, 0 if it hasn't been bought
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        self.upgrades.get(&upgrade).copied().unwrap_or(0)
+    }
+
+    /// The effect of an upgrade at the level it has been bought to
+    pub fn effect(&self, upgrade: Upgrade) -> f32 {
+        upgrade.effect(self.level(upgrade))
+    }
+
+    /// Adds a finished run to the lifetime stats, and banks its hay for the shop
+    pub fn record_run(&mut self, cause: DeathCause, duration: f32, hay: u32) {
+        self.hay += hay;
+
+        let stats = &mut self.stats;
+        stats.runs += 1;
+        stats.playtime += duration;
+        stats.total_hay += hay;
+        *stats.deaths.entry(cause).or_insert(0) += 1;
+    }
+
+    /// The lifetime stats and high scores as a JSON object
+    pub fn export_json(&self, high_scores: &HighScores) -> String {
+        let stats = &self.stats;
+        let deaths = DeathCause::ALL
+            .iter()
+            .map(|cause| format!("    \"{}\": {}", cause.id(), stats.deaths(*cause)))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        let best_scores = all_modes()
+            .into_iter()
+            .map(|mode| format!("    \"{}\": {:.0}", mode_name(mode), high_scores.get(mode)))
+            .collect::<Vec<_>>()
+            .join(",\n");
+
+        format!(
+            "{{\n  \"runs\": {},\n  \"playtime_seconds\": {:.1},\n  \"total_hay\": {},\n  \
+             \"average_run_seconds\": {:.1},\n  \"deaths\": {{\n{}\n  }},\n  \
+             \"best_scores\": {{\n{}\n  }}\n}}\n",
+            stats.runs,
+            stats.playtime,
+            stats.total_hay,
+            stats.average_run_length(),
+            deaths,
+            best_scores
+        )
+    }
+
+    /// The lifetime stats and high scores as `stat,value` CSV rows
+    pub fn export_csv(&self, high_scores: &HighScores) -> String {
+        let rows = self
+            .stats
+            .to_values()
+            .into_iter()
+            .chain(high_scores.to_values())
+            .map(|(key, value)| format!("{},{}\n", key, value))
+            .collect::<String>();
+
+        format!("stat,value\n{}", rows)
+    }
+
+    /// Whether the player has earned an achievement
+    pub fn has_achievement(&self, id: &str) -> bool {
+        self.achievements.contains(id)
+    }
+
+    /// Records an achievement, returning true if it hadn't been earned before
+    pub fn unlock_achievement(&mut self, id: &str) -> bool {
+        self.achievements.insert(id.to_string())
+    }
+
+    /// Buys the next level of an upgrade, returning false if it is maxed out or the
+    /// player can't afford it
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        let cost = match upgrade.cost(self.level(upgrade)) {
+            Some(cost) if cost <= self.hay => cost,
+            _ => return false,
+        };
+
+        self.hay -= cost;
+        *self.upgrades.entry(upgrade).or_insert(0) += 1;
+        true
+    }
+}
@@ -0,0 +1,306 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions,
+    difficulty::DifficultyPreset,
+    menu::{return_to_menu, ButtonMaterials},
+    player::{DeathCause, MAX_TRACTORS},
+    profile::Profile,
+    score::HighScores,
+    storage, GameState,
+};
+
+pub struct ProfileUiPlugin;
+
+impl Plugin for ProfileUiPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Profile).with_system(setup_profile_screen.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Profile).with_system(click_profile_button.system()),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Profile).with_system(despawn_profile_screen.system()),
+        );
+    }
+}
+
+struct ProfileItem;
+
+/// Shows where the stats were exported to
+struct ExportStatusText;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProfileButton {
+    ExportJson,
+    ExportCsv,
+    Back,
+}
+
+impl ProfileButton {
+    fn label(&self) -> &'static str {
+        match self {
+            ProfileButton::ExportJson => "Export JSON",
+            ProfileButton::ExportCsv => "Export CSV",
+            ProfileButton::Back => "Back",
+        }
+    }
+}
+
+/// Formats real seconds as hours, minutes and seconds
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// The lines of lifetime stats shown on the profile screen
+fn stat_lines(profile: &Profile, high_scores: &HighScores) -> Vec<String> {
+    let stats = &profile.stats;
+    let deaths = DeathCause::ALL
+        .iter()
+        .map(|cause| format!("{}: {}", cause.id().replace('_', " "), stats.deaths(*cause)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut lines = vec![
+        format!(
+            "{} runs, {} played, {} on average",
+            stats.runs,
+            format_duration(stats.playtime),
+            format_duration(stats.average_run_length())
+        ),
+        format!(
+            "{} hay harvested, {} left to spend",
+            stats.total_hay, profile.hay
+        ),
+        format!("Deaths - {}", deaths),
+        "Best scores".to_string(),
+    ];
+
+    for preset in DifficultyPreset::ALL.iter() {
+        let scores = (2..=MAX_TRACTORS)
+            .map(|tractors| {
+                format!(
+                    "{} tractors: {:.0}",
+                    tractors,
+                    high_scores.get((*preset, tractors))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(format!("{} - {}", preset.name(), scores));
+    }
+
+    lines
+}
+
+fn setup_profile_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    button_materials: Res<ButtonMaterials>,
+    profile: Res<Profile>,
+    high_scores: Res<HighScores>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(ProfileItem)
+        .with_children(|node| {
+            node.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        left: Val::Px(0.),
+                        right: Val::Px(0.),
+                        top: Val::Px(0.),
+                        bottom: Val::Px(20.),
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Profile".to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            for line in stat_lines(&profile, &high_scores) {
+                node.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(4.)),
+                        ..Default::default()
+                    },
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: line,
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 20.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                });
+            }
+
+            node.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    margin: Rect {
+                        left: Val::Px(0.),
+                        right: Val::Px(0.),
+                        top: Val::Px(20.),
+                        bottom: Val::Px(0.),
+                    },
+                    ..Default::default()
+                },
+                material: materials.add(Color::NONE.into()),
+                ..Default::default()
+            })
+            .with_children(|row| {
+                for button in [
+                    ProfileButton::ExportJson,
+                    ProfileButton::ExportCsv,
+                    ProfileButton::Back,
+                ]
+                .iter()
+                {
+                    row.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                            margin: Rect::all(Val::Px(10.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: button_materials.normal.clone(),
+                        ..Default::default()
+                    })
+                    .insert(*button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text {
+                                sections: vec![TextSection {
+                                    value: button.label().into(),
+                                    style: TextStyle {
+                                        font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                        font_size: 30.0,
+                                        color: Color::rgb(0.9, 0.9, 0.9),
+                                    },
+                                }],
+                                alignment: Default::default(),
+                            },
+                            ..Default::default()
+                        });
+                    });
+                }
+            });
+
+            node.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "".into(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 16.0,
+                            color: Color::rgb(0.9, 0.8, 0.2),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            })
+            .insert(ExportStatusText);
+        });
+}
+
+/// Writes the stats to a file in the save directory and describes where it went
+fn export(file_name: &str, contents: &str) -> String {
+    match storage::save_file(file_name, contents) {
+        Some(path) => format!("Exported to {}", path.display()),
+        None => format!("Unable to export {}", file_name),
+    }
+}
+
+fn click_profile_button(
+    button_materials: Res<ButtonMaterials>,
+    profile: Res<Profile>,
+    high_scores: Res<HighScores>,
+    mut actions: ResMut<Actions>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &ProfileButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut status_text: Query<&mut Text, With<ExportStatusText>>,
+) {
+    if return_to_menu(&mut actions, &mut state) {
+        return;
+    }
+
+    for (interaction, mut material, button) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                let status = match *button {
+                    ProfileButton::ExportJson => {
+                        export("profile.json", &profile.export_json(&high_scores))
+                    }
+                    ProfileButton::ExportCsv => {
+                        export("profile.csv", &profile.export_csv(&high_scores))
+                    }
+                    ProfileButton::Back => {
+                        state.set(GameState::Menu).unwrap();
+                        return;
+                    }
+                };
+
+                println!("{}", status);
+                for mut text in status_text.iter_mut() {
+                    text.sections[0].value = status.clone();
+                }
+            }
+            Interaction::Hovered => {
+                *material = button_materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = button_materials.normal.clone();
+            }
+        }
+    }
+}
+
+fn despawn_profile_screen(mut commands: Commands, items: Query<Entity, With<ProfileItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
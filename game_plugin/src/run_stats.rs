@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_time::GameTime,
+    obstacles::{ObstacleCrossedEvent, ObstacleOutcome},
+    player::{PlayerShip, STRAIN_THRESHOLDS},
+    score::{Score, CAPTURE_SCORE},
+    GameState, SystemLabels,
+};
+
+/// Real seconds between the samples drawn in the post-game graphs
+const SAMPLE_INTERVAL: f32 = 1.;
+
+/// The most bars drawn in each post-game graph, longer runs are thinned out to fit
+const GRAPH_BARS: usize = 60;
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<RunStats>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_run_stats.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_run_stats.system().after(SystemLabels::UpdateScore)),
+            );
+    }
+}
+
+/// The score and strain at a moment in the run
+#[derive(Debug, Clone, Copy)]
+pub struct RunSample {
+    pub score: f32,
+    pub strain: f32,
+}
+
+/// Statistics about the current (or just finished) run
+#[derive(Debug, Default)]
+pub struct RunStats {
+    /// Real seconds survived, ignoring the game speeding up
+    pub duration: f32,
+
+    pub hay_captured: u32,
+    pub hay_missed: u32,
+
+    /// Strain as a fraction of the strain that breaks the tether
+    pub peak_strain: f32,
+    strain_time: f32,
+
+    /// Real seconds spent above 66% strain
+    pub time_above_66: f32,
+
+    pub max_multiplier: f32,
+
+    /// Score earned by surviving and by capturing hay, which add up to the final score
+    pub score_from_time: f32,
+    pub score_from_captures: f32,
+
+    /// The score and strain every SAMPLE_INTERVAL seconds
+    pub samples: Vec<RunSample>,
+    next_sample: f32,
+}
+
+impl RunStats {
+    /// The strain averaged over the run
+    pub fn average_strain(&self) -> f32 {
+        if self.duration > 0. {
+            self.strain_time / self.duration
+        } else {
+            0.
+        }
+    }
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+/// Records the run as it is played
+fn update_run_stats(
+    time: Res<GameTime>,
+    ship: Res<PlayerShip>,
+    score: Res<Score>,
+    mut stats: ResMut<RunStats>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+) {
+    if ship.is_dead {
+        return;
+    }
+
+    for ev in crossed_events.iter() {
+        match ev.outcome {
+            ObstacleOutcome::Captured => stats.hay_captured += 1,
+            ObstacleOutcome::Missed | ObstacleOutcome::NearMiss => stats.hay_missed += 1,
+            ObstacleOutcome::HitTractor => {}
+        }
+    }
+
+    let real_delta = time.delta / time.multiplier;
+    let strain = ship.strain_ratio();
+
+    stats.duration += real_delta;
+    stats.peak_strain = stats.peak_strain.max(strain);
+    stats.strain_time += strain * real_delta;
+    if strain > STRAIN_THRESHOLDS[1] {
+        stats.time_above_66 += real_delta;
+    }
+    stats.max_multiplier = stats.max_multiplier.max(time.multiplier);
+
+    stats.score_from_captures = score.hay as f32 * CAPTURE_SCORE;
+    stats.score_from_time = score.current - stats.score_from_captures;
+
+    if stats.duration >= stats.next_sample {
+        stats.next_sample += SAMPLE_INTERVAL;
+        stats.samples.push(RunSample {
+            score: score.current,
+            strain,
+        });
+    }
+}
+
+/// Spawns the breakdown of a finished run: a summary of the stats and graphs of the score
+/// and strain over the run
+pub fn spawn_run_breakdown(
+    parent: &mut ChildBuilder,
+    stats: &RunStats,
+    asset_server: &AssetServer,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    let lines = [
+        format!(
+            "Survived {}:{:02}, reaching x{:.1} speed",
+            stats.duration as u32 / 60,
+            stats.duration as u32 % 60,
+            stats.max_multiplier
+        ),
+        format!(
+            "Captured {} hay and missed {}",
+            stats.hay_captured, stats.hay_missed
+        ),
+        format!(
+            "Scored {:.0} by surviving and {:.0} by capturing hay",
+            stats.score_from_time, stats.score_from_captures
+        ),
+        format!(
+            "Strain peaked at {:.0}%, averaged {:.0}%, and was above 66% for {:.0}s",
+            100. * stats.peak_strain,
+            100. * stats.average_strain(),
+            stats.time_above_66
+        ),
+    ];
+
+    for line in lines.iter() {
+        parent.spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: line.clone(),
+                    style: TextStyle {
+                        font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                        font_size: 16.0,
+                        color: Color::rgb(0.3, 0.3, 0.3),
+                    },
+                }],
+                alignment: Default::default(),
+            },
+            ..Default::default()
+        });
+    }
+
+    let max_score = stats
+        .samples
+        .iter()
+        .map(|sample| sample.score)
+        .fold(1., f32::max);
+    let scores = stats
+        .samples
+        .iter()
+        .map(|sample| sample.score / max_score)
+        .collect::<Vec<_>>();
+    let strains = stats
+        .samples
+        .iter()
+        .map(|sample| sample.strain.min(1.))
+        .collect::<Vec<_>>();
+
+    let background = materials.add(Color::rgba(0., 0., 0., 0.2).into());
+    let score_bar = materials.add(Color::rgb(0.9, 0.8, 0.2).into());
+    let strain_bar = materials.add(Color::rgb(0.8, 0.3, 0.3).into());
+    let transparent = materials.add(Color::NONE.into());
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                margin: Rect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            material: transparent.clone(),
+            ..Default::default()
+        })
+        .with_children(|row| {
+            for (label, values, bar) in [
+                ("Score over time", &scores, &score_bar),
+                ("Strain over time", &strains, &strain_bar),
+            ]
+            .iter()
+            {
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        margin: Rect::all(Val::Px(10.)),
+                        ..Default::default()
+                    },
+                    material: transparent.clone(),
+                    ..Default::default()
+                })
+                .with_children(|column| {
+                    column.spawn_bundle(TextBundle {
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: label.to_string(),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 16.0,
+                                    color: Color::rgb(0.3, 0.3, 0.3),
+                                },
+                            }],
+                            alignment: Default::default(),
+                        },
+                        ..Default::default()
+                    });
+
+                    spawn_graph(column, values, background.clone(), (*bar).clone());
+                });
+            }
+        });
+}
+
+/// Spawns a bar graph of values from 0 to 1
+fn spawn_graph(
+    parent: &mut ChildBuilder,
+    values: &[f32],
+    background: Handle<ColorMaterial>,
+    bar: Handle<ColorMaterial>,
+) {
+    let bars = values.len().min(GRAPH_BARS);
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(280.), Val::Px(80.)),
+                flex_direction: FlexDirection::Row,
+                // the ui's y axis points up, so this lines the bars up along the bottom
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            material: background,
+            ..Default::default()
+        })
+        .with_children(|graph| {
+            for idx in 0..bars {
+                let value = values[idx * values.len() / bars];
+                graph.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(
+                            Val::Percent(100. / bars as f32),
+                            Val::Percent(100. * value),
+                        ),
+                        ..Default::default()
+                    },
+                    material: bar.clone(),
+                    ..Default::default()
+                });
+            }
+        });
+}
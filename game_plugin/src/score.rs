@@ -1,11 +1,20 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 
 use crate::{
+    difficulty::DifficultyPreset,
     game_time::GameTime,
-    player::{PlayerShip, MAX_SEPARATION_STRAIN},
-    GameState, SystemLabels,
+    obstacles::{ObstacleCrossedEvent, ObstacleOutcome},
+    player::{PlayerShip, MAX_TRACTORS},
+    storage, GameState, SystemLabels,
 };
 
+const HIGH_SCORES_FILE: &str = "high_scores.txt";
+
+/// The score for capturing one obstacle
+pub const CAPTURE_SCORE: f32 = 10.;
+
 pub struct CapturedObstacle;
 
 pub struct ScorePlugin;
@@ -13,116 +22,128 @@ pub struct ScorePlugin;
 pub struct Score {
     pub current: f32,
     pub multiplier: f32,
+
+    /// Obstacles captured this run, paid out as hay for the shop when the run ends
+    pub hay: u32,
+
+    /// Obstacles captured in a row, without missing or hitting one
+    pub combo: u32,
+}
+
+/// A way of playing the game that keeps its own high score: a difficulty and tractor count
+pub type GameMode = (DifficultyPreset, usize);
+
+/// Every game mode, in the order they are listed on the profile screen
+pub fn all_modes() -> Vec<GameMode> {
+    DifficultyPreset::ALL
+        .iter()
+        .flat_map(|preset| (2..=MAX_TRACTORS).map(move |tractors| (*preset, tractors)))
+        .collect()
+}
+
+pub fn mode_name(mode: GameMode) -> String {
+    format!("{}, {} tractors", mode.0.name(), mode.1)
+}
+
+/// The key a mode's high score is saved and exported under
+fn mode_key(mode: GameMode) -> String {
+    format!("best_{}_{}", mode.0.name().to_lowercase(), mode.1)
 }
 
-pub struct ScoreItem;
-pub struct ScoreText;
+/// The best score achieved in each game mode, saved between runs
+pub struct HighScores {
+    scores: HashMap<GameMode, f32>,
+}
+
+impl HighScores {
+    /// Loads the high scores from the save file
+    pub fn load() -> Self {
+        let values = storage::load_values(HIGH_SCORES_FILE);
+
+        HighScores {
+            scores: all_modes()
+                .into_iter()
+                .filter_map(|mode| {
+                    // high scores saved before the tractor count could be picked are
+                    // saved under the preset's name, and were all played with 2 tractors
+                    let score = values
+                        .get(&mode_key(mode))
+                        .or_else(|| values.get(mode.0.name()).filter(|_| mode.1 == 2))?
+                        .parse::<f32>()
+                        .ok()?;
+                    Some((mode, score))
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes the high scores to the save file
+    pub fn save(&self) {
+        storage::save_values(HIGH_SCORES_FILE, &self.to_values().into_iter().collect());
+    }
+
+    /// The high score of every mode as `key=value` pairs, as saved and exported
+    pub fn to_values(&self) -> Vec<(String, String)> {
+        all_modes()
+            .into_iter()
+            .map(|mode| (mode_key(mode), format!("{:.0}", self.get(mode))))
+            .collect()
+    }
+
+    /// Gets the high score for a mode, or 0 if it hasn't been played yet
+    pub fn get(&self, mode: GameMode) -> f32 {
+        self.scores.get(&mode).copied().unwrap_or(0.)
+    }
+
+    /// Records a score for a mode, returning true if it is a new high score
+    pub fn submit(&mut self, mode: GameMode, score: f32) -> bool {
+        let score = score.floor();
+        if score <= self.get(mode) {
+            return false;
+        }
+
+        self.scores.insert(mode, score);
+        true
+    }
+}
 
 impl Default for Score {
     fn default() -> Self {
         Score {
             current: 0.,
             multiplier: 1.,
+            hay: 0,
+            combo: 0,
         }
     }
 }
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Playing).with_system(spawn_score_ui.system()),
-        )
-        .add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(
-                    update_score
-                        .system()
-                        .label(SystemLabels::UpdateScore)
-                        .after(SystemLabels::UpdateTime),
-                )
-                .with_system(score_captured_obstacles.system())
-                .with_system(
-                    update_score_text_ui
-                        .system()
-                        .after(SystemLabels::UpdateScore),
-                ),
-        )
-        .add_system_set(
-            SystemSet::on_exit(GameState::Playing).with_system(despawn_score_ui.system()),
-        );
+        app.insert_resource(HighScores::load())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_score.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        update_score
+                            .system()
+                            .label(SystemLabels::UpdateScore)
+                            .after(SystemLabels::UpdateTime),
+                    )
+                    .with_system(score_captured_obstacles.system())
+                    .with_system(update_combo.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(remove_score.system()),
+            );
     }
 }
 
-/// spawns the score UI
-fn spawn_score_ui(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+/// Starts the run with no score
+fn reset_score(mut commands: Commands) {
     commands.insert_resource(Score::default());
-
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::FlexStart,
-                align_items: AlignItems::Center,
-                flex_direction: FlexDirection::ColumnReverse,
-                ..Default::default()
-            },
-            material: materials.add(Color::NONE.into()),
-            ..Default::default()
-        })
-        .insert(ScoreItem)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![
-                            TextSection {
-                                value: "".to_string(),
-                                style: TextStyle {
-                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
-                                    font_size: 30.0,
-                                    color: Color::rgb(0.3, 0.3, 0.3),
-                                },
-                            },
-                            TextSection {
-                                value: "".to_string(),
-                                style: TextStyle {
-                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
-                                    font_size: 30.0,
-                                    color: Color::rgb(0.3, 0.3, 0.3),
-                                },
-                            },
-                        ],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                })
-                .insert(ScoreText);
-        });
-}
-
-/// Updates the score UI
-fn update_score_text_ui(
-    score: Res<Score>,
-    ship: Res<PlayerShip>,
-    mut score_text: Query<&mut Text, With<ScoreText>>,
-) {
-    for mut text in score_text.iter_mut() {
-        text.sections[0].value = format!("{:.0}, tether strain: ", score.current.floor());
-
-        text.sections[1].value = format!(
-            "{:.0}%",
-            100. * ship.separation_strain / MAX_SEPARATION_STRAIN
-        );
-        text.sections[1].style.color = Color::rgb(
-            0.3 + 0.5 * (ship.separation_strain / MAX_SEPARATION_STRAIN),
-            0.3,
-            0.3,
-        );
-    }
 }
 
 /// Increments the score by the time
@@ -146,17 +167,33 @@ fn score_captured_obstacles(
     }
 
     for entity in captured_obstacles.iter() {
-        score.current += 10.;
+        score.current += CAPTURE_SCORE;
+        score.hay += 1;
         // prevent continuously scoring from this obstacle
         commands.entity(entity).remove::<CapturedObstacle>();
     }
 }
 
-/// despawns the score ui
-fn despawn_score_ui(mut commands: Commands, items: Query<Entity, With<ScoreItem>>) {
-    commands.remove_resource::<Score>();
+/// Counts captures in a row, starting again whenever an obstacle gets past the tether
+fn update_combo(
+    ship: Res<PlayerShip>,
+    mut score: ResMut<Score>,
+    mut crossed_events: EventReader<ObstacleCrossedEvent>,
+) {
+    if ship.is_dead {
+        return;
+    }
 
-    for ent in items.iter() {
-        commands.entity(ent).despawn_recursive();
+    for ev in crossed_events.iter() {
+        match ev.outcome {
+            ObstacleOutcome::Captured => score.combo += 1,
+            ObstacleOutcome::Missed | ObstacleOutcome::NearMiss | ObstacleOutcome::HitTractor => {
+                score.combo = 0
+            }
+        }
     }
 }
+
+fn remove_score(mut commands: Commands) {
+    commands.remove_resource::<Score>();
+}
@@ -0,0 +1,614 @@
+use bevy::{prelude::*, window::WindowMode};
+
+use crate::{
+    actions::Actions,
+    difficulty::{Difficulty, DifficultyPreset},
+    menu::{return_to_menu, ButtonMaterials},
+    storage, GameState,
+};
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// The window scales the player can pick from, as multiples of the game's size
+const WINDOW_SCALES: [f32; 4] = [0.75, 1., 1.25, 1.5];
+
+/// The screen shake strengths the player can pick from, and what each is called
+const SCREEN_SHAKES: [(f32, &str); 3] = [(0., "Off"), (0.5, "Low"), (1., "Full")];
+
+const SLIDER_WIDTH: f32 = 300.;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Settings::load())
+            .add_system(apply_window_settings.system())
+            .add_system(apply_difficulty_setting.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings).with_system(setup_settings.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(click_settings_button.system())
+                    .with_system(drag_sliders.system())
+                    .with_system(update_settings_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings).with_system(despawn_settings.system()),
+            );
+    }
+}
+
+/// Audio, video and gameplay options, saved between runs and applied as soon as they change
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Volumes from 0 to 1. The music and effects volumes are scaled by the master volume.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+
+    pub fullscreen: bool,
+
+    /// How big the window is, as a multiple of the game's size
+    pub window_scale: f32,
+
+    /// How hard the camera shakes when something hits the tractors, from 0 (off) to 1
+    pub screen_shake: f32,
+
+    /// Whether the camera zooms in on combos and on the failure at the end of a run
+    pub camera_zoom: bool,
+
+    /// The difficulty runs start with
+    pub difficulty: DifficultyPreset,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.,
+            music_volume: 1.,
+            effects_volume: 1.,
+            fullscreen: false,
+            window_scale: 1.,
+            screen_shake: 1.,
+            camera_zoom: true,
+            difficulty: DifficultyPreset::Normal,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from the config file, using the defaults for anything missing
+    pub fn load() -> Self {
+        let values = storage::load_values(SETTINGS_FILE);
+        let defaults = Settings::default();
+        let volume = |key: &str, default: f32| {
+            values
+                .get(key)
+                .and_then(|value| value.parse::<f32>().ok())
+                .map(|value| value.max(0.).min(1.))
+                .unwrap_or(default)
+        };
+        let toggle = |key: &str, default: bool| {
+            values
+                .get(key)
+                .and_then(|value| value.parse::<bool>().ok())
+                .unwrap_or(default)
+        };
+
+        Settings {
+            master_volume: volume("master_volume", defaults.master_volume),
+            music_volume: volume("music_volume", defaults.music_volume),
+            effects_volume: volume("effects_volume", defaults.effects_volume),
+            fullscreen: toggle("fullscreen", defaults.fullscreen),
+            window_scale: values
+                .get("window_scale")
+                .and_then(|value| value.parse::<f32>().ok())
+                .filter(|scale| WINDOW_SCALES.contains(scale))
+                .unwrap_or(defaults.window_scale),
+            screen_shake: values
+                .get("screen_shake")
+                .and_then(|value| value.parse::<f32>().ok())
+                .filter(|shake| SCREEN_SHAKES.iter().any(|(level, _)| level == shake))
+                .unwrap_or(defaults.screen_shake),
+            camera_zoom: toggle("camera_zoom", defaults.camera_zoom),
+            difficulty: values
+                .get("difficulty")
+                .and_then(|name| {
+                    DifficultyPreset::ALL
+                        .iter()
+                        .find(|preset| preset.name() == name)
+                        .copied()
+                })
+                .unwrap_or(defaults.difficulty),
+        }
+    }
+
+    /// Writes the settings to the config file
+    pub fn save(&self) {
+        let values = vec![
+            ("master_volume", format!("{:.2}", self.master_volume)),
+            ("music_volume", format!("{:.2}", self.music_volume)),
+            ("effects_volume", format!("{:.2}", self.effects_volume)),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("window_scale", self.window_scale.to_string()),
+            ("screen_shake", self.screen_shake.to_string()),
+            ("camera_zoom", self.camera_zoom.to_string()),
+            ("difficulty", self.difficulty.name().to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+
+        storage::save_values(SETTINGS_FILE, &values);
+    }
+
+    /// How loud music plays, after the master volume
+    pub fn music(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    /// How loud sound effects play, after the master volume
+    pub fn effects(&self) -> f32 {
+        self.master_volume * self.effects_volume
+    }
+
+    /// The window scale after this one, wrapping back around to the smallest
+    fn next_window_scale(&self) -> f32 {
+        let idx = WINDOW_SCALES
+            .iter()
+            .position(|scale| *scale == self.window_scale)
+            .unwrap_or(0);
+        WINDOW_SCALES[(idx + 1) % WINDOW_SCALES.len()]
+    }
+
+    fn screen_shake_idx(&self) -> usize {
+        SCREEN_SHAKES
+            .iter()
+            .position(|(shake, _)| *shake == self.screen_shake)
+            .unwrap_or(0)
+    }
+
+    /// The screen shake strength after this one, wrapping back around to off
+    fn next_screen_shake(&self) -> f32 {
+        SCREEN_SHAKES[(self.screen_shake_idx() + 1) % SCREEN_SHAKES.len()].0
+    }
+}
+
+/// Switches fullscreen and scales the window when the settings change
+fn apply_window_settings(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    if window.mode() != mode {
+        window.set_mode(mode);
+    }
+
+    // scaling the window keeps the same view of the game, just bigger or smaller
+    let scale_factor = window.backend_scale_factor() * settings.window_scale as f64;
+    if window.scale_factor_override() != Some(scale_factor) {
+        window.set_scale_factor_override(Some(scale_factor));
+    }
+}
+
+/// Switches the difficulty when it changes in the settings
+fn apply_difficulty_setting(settings: Res<Settings>, mut difficulty: ResMut<Difficulty>) {
+    if settings.is_changed() && difficulty.preset != settings.difficulty {
+        *difficulty = settings.difficulty.settings();
+    }
+}
+
+struct SettingsItem;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Volume {
+    Master,
+    Music,
+    Effects,
+}
+
+impl Volume {
+    const ALL: [Volume; 3] = [Volume::Master, Volume::Music, Volume::Effects];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Volume::Master => "Master volume",
+            Volume::Music => "Music volume",
+            Volume::Effects => "Effects volume",
+        }
+    }
+
+    fn get(&self, settings: &Settings) -> f32 {
+        match self {
+            Volume::Master => settings.master_volume,
+            Volume::Music => settings.music_volume,
+            Volume::Effects => settings.effects_volume,
+        }
+    }
+
+    fn set(&self, settings: &mut Settings, volume: f32) {
+        match self {
+            Volume::Master => settings.master_volume = volume,
+            Volume::Music => settings.music_volume = volume,
+            Volume::Effects => settings.effects_volume = volume,
+        }
+    }
+}
+
+/// A bar that sets a volume to wherever it is clicked or dragged
+struct VolumeSlider(Volume);
+
+/// The filled part of a volume slider
+struct SliderFill(Volume);
+
+/// The percentage shown next to a volume slider
+struct SliderText(Volume);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsButton {
+    Fullscreen,
+    WindowScale,
+    ScreenShake,
+    CameraZoom,
+    Difficulty,
+    Back,
+}
+
+impl SettingsButton {
+    fn label(&self, settings: &Settings) -> String {
+        let on_off = |enabled: bool| if enabled { "On" } else { "Off" };
+
+        match self {
+            SettingsButton::Fullscreen => format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            SettingsButton::WindowScale => format!("Window scale: {}x", settings.window_scale),
+            SettingsButton::ScreenShake => {
+                format!(
+                    "Screen shake: {}",
+                    SCREEN_SHAKES[settings.screen_shake_idx()].1
+                )
+            }
+            SettingsButton::CameraZoom => format!("Camera zoom: {}", on_off(settings.camera_zoom)),
+            SettingsButton::Difficulty => format!("Difficulty: {}", settings.difficulty.name()),
+            SettingsButton::Back => "Back".into(),
+        }
+    }
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    button_materials: Res<ButtonMaterials>,
+    settings: Res<Settings>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let transparent = materials.add(Color::NONE.into());
+    let slider_track = materials.add(Color::rgb(0.3, 0.3, 0.3).into());
+    let slider_fill = materials.add(Color::rgb(0.9, 0.8, 0.2).into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(SettingsItem)
+        .with_children(|node| {
+            node.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        left: Val::Px(0.),
+                        right: Val::Px(0.),
+                        top: Val::Px(0.),
+                        bottom: Val::Px(20.),
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Settings".to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            for volume in Volume::ALL.iter() {
+                node.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        margin: Rect::all(Val::Px(8.)),
+                        ..Default::default()
+                    },
+                    material: transparent.clone(),
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    row.spawn_bundle(TextBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(160.), Val::Auto),
+                            ..Default::default()
+                        },
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: volume.name().into(),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            }],
+                            alignment: Default::default(),
+                        },
+                        ..Default::default()
+                    });
+
+                    row.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(SLIDER_WIDTH), Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        material: slider_track.clone(),
+                        ..Default::default()
+                    })
+                    .insert(VolumeSlider(*volume))
+                    .with_children(|slider| {
+                        slider
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(
+                                        Val::Percent(100. * volume.get(&settings)),
+                                        Val::Percent(100.),
+                                    ),
+                                    ..Default::default()
+                                },
+                                material: slider_fill.clone(),
+                                ..Default::default()
+                            })
+                            .insert(SliderFill(*volume));
+                    });
+
+                    row.spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: Rect {
+                                left: Val::Px(10.),
+                                right: Val::Px(0.),
+                                top: Val::Px(0.),
+                                bottom: Val::Px(0.),
+                            },
+                            size: Size::new(Val::Px(60.), Val::Auto),
+                            ..Default::default()
+                        },
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: volume_label(volume.get(&settings)),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            }],
+                            alignment: Default::default(),
+                        },
+                        ..Default::default()
+                    })
+                    .insert(SliderText(*volume));
+                });
+            }
+
+            for buttons in [
+                &[SettingsButton::Fullscreen, SettingsButton::WindowScale][..],
+                &[SettingsButton::ScreenShake, SettingsButton::CameraZoom],
+                &[SettingsButton::Difficulty],
+            ]
+            .iter()
+            {
+                node.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                    },
+                    material: transparent.clone(),
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    for button in buttons.iter() {
+                        spawn_settings_button(
+                            row,
+                            &asset_server,
+                            &button_materials,
+                            &settings,
+                            *button,
+                        );
+                    }
+                });
+            }
+
+            spawn_settings_button(
+                node,
+                &asset_server,
+                &button_materials,
+                &settings,
+                SettingsButton::Back,
+            );
+        });
+}
+
+fn spawn_settings_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    button_materials: &ButtonMaterials,
+    settings: &Settings,
+    button: SettingsButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(260.0), Val::Px(50.0)),
+                margin: Rect::all(Val::Px(10.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: button.label(settings),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 24.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+        });
+}
+
+fn volume_label(volume: f32) -> String {
+    format!("{:.0}%", 100. * volume)
+}
+
+fn click_settings_button(
+    button_materials: Res<ButtonMaterials>,
+    mut actions: ResMut<Actions>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &SettingsButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    if return_to_menu(&mut actions, &mut state) {
+        return;
+    }
+
+    for (interaction, mut material, button) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                match *button {
+                    SettingsButton::Fullscreen => settings.fullscreen = !settings.fullscreen,
+                    SettingsButton::WindowScale => {
+                        settings.window_scale = settings.next_window_scale()
+                    }
+                    SettingsButton::ScreenShake => {
+                        settings.screen_shake = settings.next_screen_shake()
+                    }
+                    SettingsButton::CameraZoom => settings.camera_zoom = !settings.camera_zoom,
+                    SettingsButton::Difficulty => settings.difficulty = settings.difficulty.next(),
+                    SettingsButton::Back => {
+                        state.set(GameState::Menu).unwrap();
+                        return;
+                    }
+                }
+                settings.save();
+            }
+            Interaction::Hovered => {
+                *material = button_materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = button_materials.normal.clone();
+            }
+        }
+    }
+}
+
+/// Sets a volume from where the mouse is on its slider while the slider is held down
+fn drag_sliders(
+    windows: Res<Windows>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut settings: ResMut<Settings>,
+    sliders: Query<(&Interaction, &GlobalTransform, &Node, &VolumeSlider)>,
+) {
+    let cursor = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    for (interaction, transform, node, slider) in sliders.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        // ui nodes are positioned by their centre
+        let left = transform.translation.x - node.size.x / 2.;
+        let volume = ((cursor.x - left) / node.size.x).max(0.).min(1.);
+        if (slider.0.get(&settings) - volume).abs() > 0.005 {
+            slider.0.set(&mut settings, volume);
+        }
+    }
+
+    // only save once the slider is let go
+    if mouse_input.just_released(MouseButton::Left) {
+        settings.save();
+    }
+}
+
+/// Refreshes the sliders and button labels when the settings change
+fn update_settings_ui(
+    settings: Res<Settings>,
+    mut fills: Query<(&mut Style, &SliderFill)>,
+    mut slider_text: Query<(&mut Text, &SliderText)>,
+    buttons: Query<(&SettingsButton, &Children)>,
+    mut button_text: Query<&mut Text, Without<SliderText>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (mut style, fill) in fills.iter_mut() {
+        style.size.width = Val::Percent(100. * fill.0.get(&settings));
+    }
+
+    for (mut text, slider) in slider_text.iter_mut() {
+        text.sections[0].value = volume_label(slider.0.get(&settings));
+    }
+
+    for (button, children) in buttons.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = button_text.get_mut(*child) {
+                text.sections[0].value = button.label(&settings);
+            }
+        }
+    }
+}
+
+fn despawn_settings(mut commands: Commands, items: Query<Entity, With<SettingsItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
@@ -0,0 +1,328 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions,
+    menu::{return_to_menu, ButtonMaterials},
+    profile::Profile,
+    GameState,
+};
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Profile::load())
+            .add_system_set(SystemSet::on_enter(GameState::Shop).with_system(setup_shop.system()))
+            .add_system_set(
+                SystemSet::on_update(GameState::Shop)
+                    .with_system(click_shop_button.system())
+                    .with_system(update_shop_text.system()),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Shop).with_system(despawn_shop.system()));
+    }
+}
+
+/// Persistent upgrades bought in the shop with hay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Upgrade {
+    Speed,
+    TetherLength,
+    StrainRecovery,
+    Shield,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [
+        Upgrade::Speed,
+        Upgrade::TetherLength,
+        Upgrade::StrainRecovery,
+        Upgrade::Shield,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::Speed => "Speed",
+            Upgrade::TetherLength => "Tether length",
+            Upgrade::StrainRecovery => "Strain recovery",
+            Upgrade::Shield => "Shield",
+        }
+    }
+
+    /// The key the upgrade's level is saved under in the profile
+    pub fn key(&self) -> &'static str {
+        match self {
+            Upgrade::Speed => "upgrade_speed",
+            Upgrade::TetherLength => "upgrade_tether_length",
+            Upgrade::StrainRecovery => "upgrade_strain_recovery",
+            Upgrade::Shield => "upgrade_shield",
+        }
+    }
+
+    pub fn max_level(&self) -> u32 {
+        match self {
+            Upgrade::Shield => 2,
+            _ => 3,
+        }
+    }
+
+    /// The hay it costs to buy the level after this one, or None if it is maxed out.
+    /// Each level costs twice as much as the last.
+    pub fn cost(&self, level: u32) -> Option<u32> {
+        if level >= self.max_level() {
+            return None;
+        }
+
+        let base_cost = match self {
+            Upgrade::Speed => 20,
+            Upgrade::TetherLength => 25,
+            Upgrade::StrainRecovery => 20,
+            Upgrade::Shield => 40,
+        };
+        Some(base_cost << level)
+    }
+
+    /// What the upgrade does at a level: a speed multiplier, extra tether length in
+    /// sprites, a strain recovery multiplier, or the number of shields
+    pub fn effect(&self, level: u32) -> f32 {
+        let level = level as f32;
+        match self {
+            Upgrade::Speed => 1. + 0.1 * level,
+            Upgrade::TetherLength => 0.5 * level,
+            Upgrade::StrainRecovery => 1. + 0.25 * level,
+            Upgrade::Shield => level,
+        }
+    }
+
+    /// Describes the effect of the upgrade at a level
+    pub fn describe(&self, level: u32) -> String {
+        let effect = self.effect(level);
+        match self {
+            Upgrade::Speed => format!("+{:.0}% speed", 100. * (effect - 1.)),
+            Upgrade::TetherLength => format!("+{:.1} sprites of tether", effect),
+            Upgrade::StrainRecovery => format!("+{:.0}% strain recovery", 100. * (effect - 1.)),
+            Upgrade::Shield => match level {
+                1 => "blocks 1 hit".into(),
+                _ => format!("blocks {} hits", level),
+            },
+        }
+    }
+
+    /// The shop label, previewing the next level and its cost
+    fn label(&self, profile: &Profile) -> String {
+        let level = profile.level(*self);
+        let current = if level == 0 {
+            "none".to_string()
+        } else {
+            self.describe(level)
+        };
+
+        match self.cost(level) {
+            Some(cost) => format!(
+                "{} {}/{}: {} -> {} ({} hay)",
+                self.name(),
+                level,
+                self.max_level(),
+                current,
+                self.describe(level + 1),
+                cost
+            ),
+            None => format!(
+                "{} {}/{}: {} (maxed)",
+                self.name(),
+                level,
+                self.max_level(),
+                current
+            ),
+        }
+    }
+}
+
+struct ShopItem;
+
+struct ShopHayText;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ShopButton {
+    Buy(Upgrade),
+    Back,
+}
+
+impl ShopButton {
+    fn label(&self, profile: &Profile) -> String {
+        match self {
+            ShopButton::Buy(upgrade) => upgrade.label(profile),
+            ShopButton::Back => "Back".into(),
+        }
+    }
+}
+
+fn setup_shop(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    button_materials: Res<ButtonMaterials>,
+    profile: Res<Profile>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(ShopItem)
+        .with_children(|node| {
+            node.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Tractor Shop".to_string(),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+
+            node.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        left: Val::Px(0.),
+                        right: Val::Px(0.),
+                        top: Val::Px(10.),
+                        bottom: Val::Px(10.),
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: hay_label(&profile),
+                        style: TextStyle {
+                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            })
+            .insert(ShopHayText);
+
+            let buttons = Upgrade::ALL
+                .iter()
+                .map(|upgrade| ShopButton::Buy(*upgrade))
+                .chain(std::iter::once(ShopButton::Back));
+
+            for button in buttons {
+                node.spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(600.0), Val::Px(40.0)),
+                        margin: Rect::all(Val::Px(5.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: button_materials.normal.clone(),
+                    ..Default::default()
+                })
+                .insert(button)
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: button.label(&profile),
+                                style: TextStyle {
+                                    font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            }],
+                            alignment: Default::default(),
+                        },
+                        ..Default::default()
+                    });
+                });
+            }
+        });
+}
+
+/// The hay line at the top of the shop
+fn hay_label(profile: &Profile) -> String {
+    format!("You have {} hay to spend", profile.hay)
+}
+
+fn click_shop_button(
+    button_materials: Res<ButtonMaterials>,
+    mut actions: ResMut<Actions>,
+    mut profile: ResMut<Profile>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &ShopButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    if return_to_menu(&mut actions, &mut state) {
+        return;
+    }
+
+    for (interaction, mut material, button) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => match *button {
+                ShopButton::Buy(upgrade) => {
+                    if profile.buy(upgrade) {
+                        println!("Bought {} level {}", upgrade.name(), profile.level(upgrade));
+                        profile.save();
+                    }
+                }
+                ShopButton::Back => {
+                    state.set(GameState::Menu).unwrap();
+                    return;
+                }
+            },
+            Interaction::Hovered => {
+                *material = button_materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = button_materials.normal.clone();
+            }
+        }
+    }
+}
+
+/// Refreshes the hay and upgrade previews after buying something
+fn update_shop_text(
+    profile: Res<Profile>,
+    buttons: Query<(&ShopButton, &Children)>,
+    mut button_text: Query<&mut Text, Without<ShopHayText>>,
+    mut hay_text: Query<&mut Text, With<ShopHayText>>,
+) {
+    if !profile.is_changed() {
+        return;
+    }
+
+    for mut text in hay_text.iter_mut() {
+        text.sections[0].value = hay_label(&profile);
+    }
+
+    for (button, children) in buttons.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = button_text.get_mut(*child) {
+                text.sections[0].value = button.label(&profile);
+            }
+        }
+    }
+}
+
+fn despawn_shop(mut commands: Commands, items: Query<Entity, With<ShopItem>>) {
+    for item in items.iter() {
+        commands.entity(item).despawn_recursive();
+    }
+}
@@ -0,0 +1,248 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::{obstacles::pattern_file::parse_value, profile::Profile};
+
+/// The skin used if no skin files load, matching the original tractors and tether
+pub const DEFAULT_SKIN: &str = "
+name Classic
+tractor_left textures/player_left.png 32x32 4x1 4
+tractor_right textures/player_right.png 32x32 4x1 4
+tether textures/laser.png 32x16 10x1 10
+";
+
+pub struct SkinsPlugin;
+
+impl Plugin for SkinsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<Skin>().init_asset_loader::<SkinLoader>();
+    }
+}
+
+/// A sprite sheet used by a skin, cut into a grid of equally sized frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinAtlas {
+    /// The texture path, relative to the assets folder
+    pub texture: String,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+
+    /// How many of the grid's cells are animation frames, from the top left
+    pub frames: usize,
+}
+
+impl SkinAtlas {
+    pub fn texture_atlas(&self, asset_server: &AssetServer) -> TextureAtlas {
+        TextureAtlas::from_grid(
+            asset_server.get_handle(self.texture.as_str()),
+            self.tile_size,
+            self.columns,
+            self.rows,
+        )
+    }
+}
+
+/// A cosmetic look for the tractors and tether, loaded from a `.skin` file. Each line sets
+/// one part of the skin, and `#` starts a comment:
+///
+/// ```text
+/// name <name>
+/// unlock <achievement>                        locked until the achievement is earned
+/// tractor_left <texture> <w>x<h> <columns>x<rows> <frames>
+/// tractor_right <texture> <w>x<h> <columns>x<rows> <frames>
+/// tether <texture> <w>x<h> <columns>x<rows> <frames>
+/// tractor_tint <r>,<g>,<b>                    optional, white if missing
+/// tether_tint <r>,<g>,<b>                     optional, white if missing
+/// ```
+///
+/// The rightmost tractor uses `tractor_right` and the others use `tractor_left`. The skins
+/// shipped with the game all use the original sprite sheets, and differ by their tints and
+/// how many frames they animate.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "3e8d51b0-94c7-4f2a-a6d3-0b7c2e9f1a45"]
+pub struct Skin {
+    pub name: String,
+    pub unlock: Option<String>,
+    pub tractor_left: SkinAtlas,
+    pub tractor_right: SkinAtlas,
+    pub tether: SkinAtlas,
+    pub tractor_tint: Color,
+    pub tether_tint: Color,
+}
+
+impl Default for Skin {
+    fn default() -> Self {
+        parse_skin(DEFAULT_SKIN).expect("the default skin is valid")
+    }
+}
+
+impl Skin {
+    /// The textures the skin needs loaded before it can be used
+    pub fn textures(&self) -> [&str; 3] {
+        [
+            &self.tractor_left.texture,
+            &self.tractor_right.texture,
+            &self.tether.texture,
+        ]
+    }
+
+    /// Whether the player can use this skin
+    pub fn is_unlocked(&self, profile: &Profile) -> bool {
+        match &self.unlock {
+            Some(achievement) => profile.has_achievement(achievement),
+            None => true,
+        }
+    }
+}
+
+/// Every skin that loaded, in the order they are listed in the asset paths
+pub struct Skins(pub Vec<Skin>);
+
+impl Skins {
+    /// The skin the player picked, or the first skin if it is missing or locked
+    pub fn selected(&self, profile: &Profile) -> &Skin {
+        self.0
+            .iter()
+            .find(|skin| skin.name == profile.skin && skin.is_unlocked(profile))
+            .unwrap_or(&self.0[0])
+    }
+
+    /// The next unlocked skin after the selected one, wrapping around
+    pub fn next(&self, profile: &Profile) -> &Skin {
+        let selected = self.selected(profile);
+        let idx = self
+            .0
+            .iter()
+            .position(|skin| skin.name == selected.name)
+            .unwrap_or(0);
+
+        self.0
+            .iter()
+            .cycle()
+            .skip(idx + 1)
+            .take(self.0.len())
+            .find(|skin| skin.is_unlocked(profile))
+            .unwrap_or(selected)
+    }
+}
+
+#[derive(Default)]
+pub struct SkinLoader;
+
+impl AssetLoader for SkinLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let contents = std::str::from_utf8(bytes)?;
+            let skin = parse_skin(contents).map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(skin));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["skin"]
+    }
+}
+
+/// Parses the contents of a `.skin` file
+pub fn parse_skin(contents: &str) -> Result<Skin, String> {
+    let mut name = None;
+    let mut unlock = None;
+    let mut tractor_left = None;
+    let mut tractor_right = None;
+    let mut tether = None;
+    let mut tractor_tint = Color::WHITE;
+    let mut tether_tint = Color::WHITE;
+
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let key = parts.next().unwrap_or("");
+        let parsed = match key {
+            "name" => {
+                // names can have spaces in them
+                let value = parts.collect::<Vec<_>>().join(" ");
+                if value.is_empty() {
+                    Err("missing name".to_string())
+                } else {
+                    name = Some(value);
+                    Ok(())
+                }
+            }
+            "unlock" => parse_value(parts.next(), "unlock").map(|value| unlock = Some(value)),
+            "tractor_left" => parse_atlas(&mut parts).map(|atlas| tractor_left = Some(atlas)),
+            "tractor_right" => parse_atlas(&mut parts).map(|atlas| tractor_right = Some(atlas)),
+            "tether" => parse_atlas(&mut parts).map(|atlas| tether = Some(atlas)),
+            "tractor_tint" => parse_color(parts.next()).map(|color| tractor_tint = color),
+            "tether_tint" => parse_color(parts.next()).map(|color| tether_tint = color),
+            _ => Err(format!("unknown entry '{}'", key)),
+        };
+
+        if let Err(e) = parsed {
+            return Err(format!("line {}: {}", line_idx + 1, e));
+        }
+    }
+
+    Ok(Skin {
+        name: name.ok_or("missing name")?,
+        unlock,
+        tractor_left: tractor_left.ok_or("missing tractor_left")?,
+        tractor_right: tractor_right.ok_or("missing tractor_right")?,
+        tether: tether.ok_or("missing tether")?,
+        tractor_tint,
+        tether_tint,
+    })
+}
+
+/// Parses `<texture> <w>x<h> <columns>x<rows> <frames>`
+fn parse_atlas<'a, I: Iterator<Item = &'a str>>(parts: &mut I) -> Result<SkinAtlas, String> {
+    let texture: String = parse_value(parts.next(), "texture")?;
+    let (width, height) = parse_pair(parts.next(), "tile size")?;
+    let (columns, rows) = parse_pair(parts.next(), "grid")?;
+    let frames = parse_value(parts.next(), "frames")?;
+
+    if frames == 0 || frames > columns * rows {
+        return Err(format!(
+            "{} frames don't fit a {}x{} grid",
+            frames, columns, rows
+        ));
+    }
+
+    Ok(SkinAtlas {
+        texture,
+        tile_size: Vec2::new(width, height),
+        columns,
+        rows,
+        frames,
+    })
+}
+
+/// Parses a `<a>x<b>` pair
+fn parse_pair<T: std::str::FromStr>(value: Option<&str>, name: &str) -> Result<(T, T), String> {
+    let mut ab = value.unwrap_or("").splitn(2, 'x');
+    let a = parse_value(ab.next(), name)?;
+    let b = parse_value(ab.next(), name)?;
+    Ok((a, b))
+}
+
+/// Parses a `<r>,<g>,<b>` color with each channel from 0 to 1
+fn parse_color(value: Option<&str>) -> Result<Color, String> {
+    let mut rgb = value.unwrap_or("").splitn(3, ',');
+    let r = parse_value(rgb.next(), "red")?;
+    let g = parse_value(rgb.next(), "green")?;
+    let b = parse_value(rgb.next(), "blue")?;
+    Ok(Color::rgb(r, g, b))
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The directory (inside the user's home directory) that save files are written to
+const SAVE_DIR: &str = ".hyper_farmer";
+
+/// Gets the path to a save file, or None if saving isn't supported on this platform
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_path(file_name: &str) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("APPDATA"))?;
+    Some(PathBuf::from(home).join(SAVE_DIR).join(file_name))
+}
+
+/// Gets the path to a save file, or None if saving isn't supported on this platform
+#[cfg(target_arch = "wasm32")]
+pub fn save_path(_file_name: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Gets the path to a file in the assets folder so game data can be written back to it,
+/// or None if the game isn't running from a folder it can write to. Mirrors how the
+/// asset server finds the assets folder.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn asset_path(relative_path: &str) -> Option<PathBuf> {
+    let root = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_exe().ok()?.parent()?.to_path_buf(),
+    };
+    Some(root.join("assets").join(relative_path))
+}
+
+/// Gets the path to a file in the assets folder, which can't be written on the web
+#[cfg(target_arch = "wasm32")]
+pub fn asset_path(_relative_path: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Loads a save file of `key=value` lines. Missing or unreadable files are treated
+/// as empty so a fresh install starts with defaults.
+pub fn load_values(file_name: &str) -> HashMap<String, String> {
+    save_path(file_name)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|contents| parse_values(&contents))
+        .unwrap_or_default()
+}
+
+/// Writes a save file of `key=value` lines, sorted by key
+pub fn save_values(file_name: &str, values: &HashMap<String, String>) {
+    let mut keys = values.keys().collect::<Vec<_>>();
+    keys.sort();
+    let contents = keys
+        .iter()
+        .map(|key| format!("{}={}\n", key, values[*key]))
+        .collect::<String>();
+
+    save_file(file_name, &contents);
+}
+
+/// Writes a file to the save directory, returning where it was written or None if it
+/// couldn't be
+pub fn save_file(file_name: &str, contents: &str) -> Option<PathBuf> {
+    let path = save_path(file_name)?;
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            println!("Unable to create save directory {:?}: {}", dir, e);
+            return None;
+        }
+    }
+
+    if let Err(e) = std::fs::write(&path, contents) {
+        println!("Unable to write save file {:?}: {}", path, e);
+        return None;
+    }
+
+    Some(path)
+}
+
+/// Parses `key=value` lines, skipping blank lines, comments and malformed lines
+fn parse_values(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next()?.trim();
+            let value = parts.next()?.trim();
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
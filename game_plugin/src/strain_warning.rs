@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy_kira_audio::Audio;
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+    Gilrs,
+};
+
+use crate::{
+    audio::AudioChannels,
+    loading::AudioAssets,
+    player::{PlayerShip, STRAIN_THRESHOLDS},
+    settings::Settings,
+    GameState,
+};
+
+/// For each of the STRAIN_THRESHOLDS: the volume of the warning beeps, how fast they loop,
+/// the strength of the rumble (out of u16::MAX) and the real seconds between rumbles
+const WARNING_LEVELS: [(f32, f32, u16, f32); 3] = [
+    (0.3, 1., 16_000, 1.),
+    (0.5, 1.6, 32_000, 0.6),
+    (0.8, 2.5, 60_000, 0.3),
+];
+
+/// How long each rumble lasts, in milliseconds
+const RUMBLE_MS: u32 = 120;
+
+pub struct StrainWarningPlugin;
+
+impl Plugin for StrainWarningPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<StrainWarning>()
+            .insert_non_send_resource(Rumble::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(start_strain_warning.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_strain_warning.system())
+                    .with_system(rumble_strain_warning.exclusive_system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(stop_strain_warning.system()),
+            );
+    }
+}
+
+/// How close the tether is to breaking, from 0 (no warning) to 3 (about to snap)
+#[derive(Default)]
+pub struct StrainWarning {
+    pub level: usize,
+}
+
+/// The gamepad rumble currently playing, kept alive until the next one replaces it.
+/// Gamepad effects aren't thread safe, so this lives in a non-send resource.
+#[derive(Default)]
+struct Rumble {
+    effect: Option<Effect>,
+    next_rumble: f32,
+}
+
+/// Starts the warning loop silently, so it can fade in as soon as the tether strains
+fn start_strain_warning(
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    mut warning: ResMut<StrainWarning>,
+) {
+    *warning = StrainWarning::default();
+    audio.set_volume_in_channel(0., &channels.strain_warning);
+    audio.play_looped_in_channel(
+        audio_assets.strain_warning.clone(),
+        &channels.strain_warning,
+    );
+}
+
+fn stop_strain_warning(audio: Res<Audio>, channels: Res<AudioChannels>) {
+    audio.stop_channel(&channels.strain_warning);
+}
+
+/// Beeps louder and faster as the strain climbs through the warning levels
+fn update_strain_warning(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    settings: Res<Settings>,
+    ship: Res<PlayerShip>,
+    mut warning: ResMut<StrainWarning>,
+) {
+    let strain = if ship.is_dead {
+        0.
+    } else {
+        ship.strain_ratio()
+    };
+    let level = STRAIN_THRESHOLDS
+        .iter()
+        .filter(|threshold| strain >= **threshold)
+        .count();
+
+    if level == warning.level && !settings.is_changed() {
+        return;
+    }
+    warning.level = level;
+
+    match level {
+        0 => audio.set_volume_in_channel(0., &channels.strain_warning),
+        _ => {
+            let (volume, playback_rate, _, _) = WARNING_LEVELS[level - 1];
+            audio.set_volume_in_channel(volume * settings.effects(), &channels.strain_warning);
+            audio.set_playback_rate_in_channel(playback_rate, &channels.strain_warning);
+        }
+    }
+}
+
+/// Rumbles any gamepads that support it, more strongly and more often at higher warning
+/// levels. Gamepads aren't available on every platform, and only the world can be asked
+/// for a non-send resource that might be missing, so this is an exclusive system.
+fn rumble_strain_warning(world: &mut World) {
+    let world = world.cell();
+    let mut gilrs = match world.get_non_send_mut::<Gilrs>() {
+        Some(gilrs) => gilrs,
+        None => return,
+    };
+    let mut rumble = world.get_non_send_mut::<Rumble>().unwrap();
+    let warning = world.get_resource::<StrainWarning>().unwrap();
+    let time = world.get_resource::<Time>().unwrap();
+
+    rumble.next_rumble -= time.delta_seconds();
+    if warning.level == 0 {
+        rumble.next_rumble = 0.;
+        return;
+    }
+    if rumble.next_rumble > 0. {
+        return;
+    }
+
+    let (_, _, magnitude, interval) = WARNING_LEVELS[warning.level - 1];
+    rumble.next_rumble = interval;
+
+    let gamepads = gilrs
+        .gamepads()
+        .filter(|(_, gamepad)| gamepad.is_ff_supported())
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    if gamepads.is_empty() {
+        return;
+    }
+
+    let effect = EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong { magnitude },
+            scheduling: Replay {
+                play_for: Ticks::from_ms(RUMBLE_MS),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .gamepads(&gamepads)
+        .finish(&mut gilrs);
+
+    match effect.and_then(|effect| effect.play().map(|_| effect)) {
+        Ok(effect) => rumble.effect = Some(effect),
+        Err(e) => println!("Unable to rumble gamepads: {}", e),
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+
+/// How many segments the tether is simulated and drawn with
+pub const TETHER_SEGMENTS: usize = 12;
+
+/// How much a bale of captured hay weighs down the tether, as extra gravity at the point
+/// it was caught
+pub const HAY_WEIGHT: f32 = 10.;
+
+/// Downwards acceleration on the tether, in world units per second squared
+const GRAVITY: f32 = 600.;
+
+/// How much of its velocity a tether point keeps each step
+const DAMPING: f32 = 0.98;
+
+/// How much of a segment's stretch is corrected per constraint pass. Lower is stretchier.
+const STIFFNESS: f32 = 0.6;
+const CONSTRAINT_ITERATIONS: usize = 8;
+
+/// The longest time step the tether is simulated with, longer frames are split up
+const MAX_STEP: f32 = 1. / 120.;
+
+/// Seconds for captured hay to settle, after which it no longer weighs the tether down
+const HAY_SETTLE_TIME: f32 = 3.;
+
+/// Stretch (as a fraction of the tether length) allowed before the tether counts as taut
+const SLACK_STRETCH: f32 = 0.02;
+
+/// Stretch at which strain builds at one unit per second
+const TAUT_STRETCH: f32 = 0.25;
+
+/// The slowest and fastest that strain builds while the tether is taut
+const MIN_STRAIN_RATE: f32 = 0.5;
+const MAX_STRAIN_RATE: f32 = 2.;
+
+/// An elastic rope between two neighbouring tractors, simulated as a chain of points. The rope sags
+/// under its own weight when slack, and its tension is what strains the tether.
+pub struct Tether {
+    points: Vec<Vec2>,
+    previous: Vec<Vec2>,
+
+    /// Extra weight on each point from captured hay
+    loads: Vec<f32>,
+
+    segment_length: f32,
+
+    /// How far the tether is stretched past its length, as a fraction of its length
+    stretch: f32,
+}
+
+impl Tether {
+    /// Creates a tether of the given length, stretched straight between two points
+    pub fn new(start: Vec2, end: Vec2, length: f32) -> Self {
+        let points = (0..=TETHER_SEGMENTS)
+            .map(|idx| start.lerp(end, idx as f32 / TETHER_SEGMENTS as f32))
+            .collect::<Vec<_>>();
+
+        Tether {
+            previous: points.clone(),
+            points,
+            loads: vec![0.; TETHER_SEGMENTS + 1],
+            segment_length: length / TETHER_SEGMENTS as f32,
+            stretch: 0.,
+        }
+    }
+
+    /// The points along the tether, from the left tractor to the right
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    /// Whether x is between the two ends of the tether
+    pub fn spans(&self, x: f32) -> bool {
+        let (start, end) = (self.points[0].x, self.points[TETHER_SEGMENTS].x);
+        x >= start.min(end) && x <= start.max(end)
+    }
+
+    /// The height of the tether where it passes x, if it spans x
+    pub fn y_at(&self, x: f32) -> Option<f32> {
+        self.points.windows(2).find_map(|pair| {
+            let (left, right) = if pair[0].x <= pair[1].x {
+                (pair[0], pair[1])
+            } else {
+                (pair[1], pair[0])
+            };
+            if x < left.x || x > right.x {
+                None
+            } else if right.x - left.x < f32::EPSILON {
+                Some(left.y.min(right.y))
+            } else {
+                Some(left.y + (right.y - left.y) * (x - left.x) / (right.x - left.x))
+            }
+        })
+    }
+
+    /// Whether the tether is stretched past its length
+    pub fn is_taut(&self) -> bool {
+        self.stretch > SLACK_STRETCH
+    }
+
+    /// How quickly strain builds from the tether's tension, in strain per second
+    pub fn strain_rate(&self) -> f32 {
+        if self.is_taut() {
+            (self.stretch / TAUT_STRETCH)
+                .max(MIN_STRAIN_RATE)
+                .min(MAX_STRAIN_RATE)
+        } else {
+            0.
+        }
+    }
+
+    /// Hangs weight on the tether at the point closest to x
+    pub fn add_load(&mut self, x: f32, weight: f32) {
+        let closest = (1..TETHER_SEGMENTS).min_by(|a, b| {
+            let da = (self.points[*a].x - x).abs();
+            let db = (self.points[*b].x - x).abs();
+            da.partial_cmp(&db).unwrap()
+        });
+
+        if let Some(idx) = closest {
+            self.loads[idx] += weight;
+        }
+    }
+
+    /// Moves the ends of the tether to the tractors and simulates the rest of it
+    pub fn update(&mut self, start: Vec2, end: Vec2, delta: f32) {
+        let steps = (delta / MAX_STEP).ceil().max(1.) as usize;
+        let dt = delta / steps as f32;
+
+        let settle = (-delta / HAY_SETTLE_TIME).exp();
+        for load in self.loads.iter_mut() {
+            *load *= settle;
+        }
+
+        for _ in 0..steps {
+            self.step(start, end, dt);
+        }
+
+        let length = self
+            .points
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .sum::<f32>();
+        let rest_length = self.segment_length * TETHER_SEGMENTS as f32;
+        self.stretch = (length / rest_length - 1.).max(0.);
+    }
+
+    fn step(&mut self, start: Vec2, end: Vec2, dt: f32) {
+        let last = self.points.len() - 1;
+
+        self.points[0] = start;
+        self.points[last] = end;
+        self.previous[0] = start;
+        self.previous[last] = end;
+
+        // verlet integration of the free points
+        for idx in 1..last {
+            let velocity = (self.points[idx] - self.previous[idx]) * DAMPING;
+            let gravity = Vec2::new(0., -GRAVITY * (1. + self.loads[idx]) * dt * dt);
+            self.previous[idx] = self.points[idx];
+            self.points[idx] += velocity + gravity;
+        }
+
+        // pull stretched segments back towards their length, like a rope it can go slack
+        for _ in 0..CONSTRAINT_ITERATIONS {
+            for idx in 0..last {
+                let offset = self.points[idx + 1] - self.points[idx];
+                let length = offset.length();
+                if length <= self.segment_length || length < f32::EPSILON {
+                    continue;
+                }
+
+                let correction = offset * (STIFFNESS * (length - self.segment_length) / length);
+                match (idx == 0, idx + 1 == last) {
+                    (true, true) => {}
+                    (true, false) => self.points[idx + 1] -= correction,
+                    (false, true) => self.points[idx] += correction,
+                    (false, false) => {
+                        self.points[idx] += correction * 0.5;
+                        self.points[idx + 1] -= correction * 0.5;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The tethers between each pair of neighbouring tractors, from the leftmost pair
+pub struct Tethers(pub Vec<Tether>);
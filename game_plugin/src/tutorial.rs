@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions,
+    game_map::GameMap,
+    game_time::GameTime,
+    loading::TextureAssets,
+    obstacles::{patterns::SpawnPattern, spawn_pattern_obstacles, Obstacle},
+    player::{PlayerShip, PlayerShipSide, STRAIN_THRESHOLDS},
+    score::CapturedObstacle,
+    GameState, SystemLabels,
+};
+
+/// How long (in seconds) a tractor needs to be moved to complete a movement step
+const MOVE_STEP_DURATION: f32 = 1.;
+
+/// The steps of the tutorial, in the order they are completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialStep {
+    MoveLeftTractor,
+    MoveRightTractor,
+    CaptureCloud,
+    FeelStrain,
+    RecoverStrain,
+    Complete,
+}
+
+impl TutorialStep {
+    /// The on-screen prompt for this step
+    fn prompt(&self) -> &'static str {
+        match self {
+            TutorialStep::MoveLeftTractor => {
+                "Use A and D to move the left tractor, and W and S to drive it up and down"
+            }
+            TutorialStep::MoveRightTractor => {
+                "Use J and L to move the right tractor, and I and K to drive it up and down"
+            }
+            TutorialStep::CaptureCloud => {
+                "Catch the falling hay by letting it pass through the laser tether"
+            }
+            TutorialStep::FeelStrain => {
+                "Move the tractors far apart and watch the tether strain rise"
+            }
+            TutorialStep::RecoverStrain => {
+                "Quick! Move the tractors closer together until the strain is gone"
+            }
+            TutorialStep::Complete => "You're ready to farm! Hit space to return to the menu",
+        }
+    }
+
+    /// The step that follows this one
+    fn next(&self) -> Self {
+        match self {
+            TutorialStep::MoveLeftTractor => TutorialStep::MoveRightTractor,
+            TutorialStep::MoveRightTractor => TutorialStep::CaptureCloud,
+            TutorialStep::CaptureCloud => TutorialStep::FeelStrain,
+            TutorialStep::FeelStrain => TutorialStep::RecoverStrain,
+            TutorialStep::RecoverStrain | TutorialStep::Complete => TutorialStep::Complete,
+        }
+    }
+}
+
+/// Tracks progress through the scripted tutorial. The tutorial runs as a practice
+/// run, so failures don't end it
+pub struct Tutorial {
+    pub enabled: bool,
+    pub step: TutorialStep,
+    progress: f32,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Tutorial {
+            enabled: false,
+            step: TutorialStep::MoveLeftTractor,
+            progress: 0.,
+        }
+    }
+}
+
+impl Tutorial {
+    /// Moves on to the next step of the tutorial
+    fn advance(&mut self) {
+        self.step = self.step.next();
+        self.progress = 0.;
+    }
+}
+
+pub struct TutorialUiItem;
+pub struct TutorialText;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Tutorial>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_tutorial_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(advance_tutorial.system().after(SystemLabels::MovePlayer))
+                    .with_system(update_tutorial_text_ui.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_tutorial_ui.system()),
+            );
+    }
+}
+
+/// Spawns the tutorial prompt, if the tutorial is running
+fn spawn_tutorial_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tutorial: ResMut<Tutorial>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !tutorial.enabled {
+        return;
+    }
+
+    tutorial.step = TutorialStep::MoveLeftTractor;
+    tutorial.progress = 0.;
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(TutorialUiItem)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: "".to_string(),
+                            style: TextStyle {
+                                font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
+                                font_size: 20.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                })
+                .insert(TutorialText);
+        });
+}
+
+/// Checks if the player has completed the current tutorial step, and spawns
+/// the hay for the capture step
+fn advance_tutorial(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    actions: Res<Actions>,
+    game_map: Res<GameMap>,
+    textures: Res<TextureAssets>,
+    ship: Res<PlayerShip>,
+    mut tutorial: ResMut<Tutorial>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    ship_sides: Query<&Transform, With<PlayerShipSide>>,
+    obstacles: Query<Entity, With<Obstacle>>,
+    captured_obstacles: Query<Entity, Added<CapturedObstacle>>,
+) {
+    if !tutorial.enabled {
+        return;
+    }
+
+    match tutorial.step {
+        TutorialStep::MoveLeftTractor => {
+            if actions.tractor_moves[0] != 0 {
+                tutorial.progress += time.delta;
+            }
+
+            if tutorial.progress > MOVE_STEP_DURATION {
+                tutorial.advance();
+            }
+        }
+        TutorialStep::MoveRightTractor => {
+            if actions.tractor_moves[1] != 0 {
+                tutorial.progress += time.delta;
+            }
+
+            if tutorial.progress > MOVE_STEP_DURATION {
+                tutorial.advance();
+            }
+        }
+        TutorialStep::CaptureCloud => {
+            if captured_obstacles.iter().next().is_some() {
+                tutorial.advance();
+                return;
+            }
+
+            // keep dropping hay between the tractors until one is caught
+            if obstacles.iter().next().is_none() {
+                let count = ship_sides.iter().count() as f32;
+                let center = ship_sides.iter().map(|tx| tx.translation.x).sum::<f32>() / count;
+                let spawn_x = (center / game_map.sprite_size).round() * game_map.sprite_size;
+
+                spawn_pattern_obstacles(
+                    &mut commands,
+                    &textures,
+                    &game_map,
+                    &mut texture_atlases,
+                    spawn_x,
+                    &SpawnPattern::default(),
+                );
+            }
+        }
+        TutorialStep::FeelStrain => {
+            if ship.strain_ratio() > STRAIN_THRESHOLDS[0] {
+                tutorial.advance();
+            }
+        }
+        TutorialStep::RecoverStrain => {
+            if ship.strain_ratio() <= 0. {
+                tutorial.advance();
+            }
+        }
+        TutorialStep::Complete => {}
+    }
+}
+
+/// Shows the prompt for the current tutorial step
+fn update_tutorial_text_ui(
+    tutorial: Res<Tutorial>,
+    mut tutorial_text: Query<&mut Text, With<TutorialText>>,
+) {
+    for mut text in tutorial_text.iter_mut() {
+        text.sections[0].value = tutorial.step.prompt().to_string();
+    }
+}
+
+/// despawns the tutorial ui
+fn despawn_tutorial_ui(mut commands: Commands, items: Query<Entity, With<TutorialUiItem>>) {
+    for ent in items.iter() {
+        commands.entity(ent).despawn_recursive();
+    }
+}